@@ -1,45 +1,341 @@
+use crate::scanner::token::{SourceId, Span};
 use colored::Colorize as Colourise;
+use serde_json::{json, Value};
 use std::fmt::{Display, Formatter};
 
-pub fn report(
-    line: u32,
-    column: u32,
-    log_level: LogLevel,
-    location: &str,
-    message: &str,
-    source: &str,
-) {
-    eprintln!(
-        "{} {}: {}\n {} line {} : column {}\n   {} {}\t{}\n",
-        log_level.to_string(),
-        location.bold(),
-        message,
-        "-->".bold().blue(),
-        &line.to_string().bold(),
-        &column.to_string().bold(),
-        line.to_string().blue().bold(),
-        "|".blue().bold(),
-        source.lines().nth(line as usize - 1).unwrap_or(""),
-    );
-}
-
-#[derive(Clone, Copy)]
+// Something scanning, parsing or running a script has to say about it. Nothing prints these
+// as they're found, they're handed back to whoever ran the script to report or keep.
+//
+// Codes say roughly what went wrong:
+//   E0001 unexpected character, E0002 unterminated string,
+//   E0100 syntax error, W0100 match may not be exhaustive,
+//   E0200 runtime error that isn't any of the below, E0201 isn't used any more,
+//   E0202 wrong type, E0203 undefined variable, property or export,
+//   E0204 import failed, E0205 division by zero, E0206 wrong number of arguments,
+//   E0207 index out of range, E0208 value doesn't match the pattern,
+//   E0209 uncaught exception, E0210 the OS said no, like a file that can't be read,
+//   E0211 a value that can't be used, like malformed JSON or an empty Deque.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: LogLevel,
+    pub code: &'static str,
+    pub message: String,
+    // What's underlined with ^^^.
+    pub span: Span,
+    // Other parts of the source that help explain it, underlined with --- and their message.
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    // Suggestions for fixing it.
+    pub help: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(severity: LogLevel, code: &'static str, message: &str, span: Span) -> Diagnostic {
+        Diagnostic {
+            severity,
+            code,
+            message: message.to_string(),
+            span,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self.severity, LogLevel::Error)
+    }
+
+    // The coloured text `report` prints, laid out like rustc's: the lines the spans are on,
+    // underlined, with any notes and help after them.
+    //
+    //     error[E0202]: Operands of '-' must be Numbers.
+    //      --> script.hb:3:11
+    //       |
+    //     3 | print "a" - 1
+    //       |           ^
+    //       |       --- left operand is String
+    //       |             - right operand is Number
+    //
+    // Each span is shown from its own source, which might not be the one that was run.
+    pub fn render(&self, sources: &Sources) -> String {
+        let mut marks = vec![Mark::new(sources, self.span, None)];
+        for label in self.labels.iter() {
+            marks.push(Mark::new(sources, label.span, Some(&label.message)));
+        }
+        // The primary span's source first, by line, and the primary mark first on its line.
+        let source = self.span.source;
+        marks.sort_by_key(|x| {
+            let span = x.span;
+            (
+                span.source != source,
+                span.source.0,
+                span.line,
+                x.label.is_some(),
+                span.start,
+            )
+        });
+
+        let gutter = marks
+            .iter()
+            .map(|x| x.span.line)
+            .max()
+            .unwrap_or(0)
+            .to_string()
+            .len();
+        let pad = " ".repeat(gutter);
+        let bar = "|".blue().bold();
+
+        let mut out = format!(
+            "{}[{}]: {}\n{}{} {}:{}:{}\n",
+            self.severity,
+            self.code,
+            self.message.bold(),
+            pad,
+            "-->".blue().bold(),
+            sources.name(source),
+            self.span.line,
+            self.span.column,
+        );
+        out += &format!("{} {}\n", pad, bar);
+
+        let mut line = None;
+        for mark in marks.iter() {
+            let text = match &mark.text {
+                Some(x) => x,
+                // The source isn't known, there's nothing to show.
+                None => continue,
+            };
+            let span = mark.span;
+            if span.source != line.map_or(source, |(x, _)| x) {
+                out += &format!(
+                    "{}{} {}:{}:{}\n",
+                    pad,
+                    ":::".blue().bold(),
+                    sources.name(span.source),
+                    span.line,
+                    span.column
+                );
+            }
+            if line != Some((span.source, span.line)) {
+                let number = format!("{:>1$}", span.line, gutter);
+                out += &format!("{} {} {}\n", number.blue().bold(), bar, text);
+                line = Some((span.source, span.line));
+            }
+
+            let underline = match mark.label {
+                None => self.severity.paint(&"^".repeat(mark.width)),
+                Some(_) => "-".repeat(mark.width).blue().bold().to_string(),
+            };
+            out += &format!("{} {} {}{}", pad, bar, " ".repeat(mark.column), underline);
+            if let Some(x) = &mark.label {
+                out += &format!(" {}", x.blue().bold());
+            }
+            out += "\n";
+        }
+
+        if !self.notes.is_empty() || !self.help.is_empty() {
+            out += &format!("{} {}\n", pad, bar);
+        }
+        for note in self.notes.iter() {
+            out += &format!(
+                "{} {} {}: {}\n",
+                pad,
+                "=".blue().bold(),
+                "note".bold(),
+                note
+            );
+        }
+        for help in self.help.iter() {
+            out += &format!(
+                "{} {} {}: {}\n",
+                pad,
+                "=".blue().bold(),
+                "help".bold(),
+                help
+            );
+        }
+        out
+    }
+
+    // The same thing for tools rather than people, like an editor or CI annotating the source.
+    // Lines and columns count from 1 and columns are in chars, the end is just past the span.
+    // Spans have their own file, in case a label's is in a different one.
+    pub fn to_json(&self, sources: &Sources) -> Value {
+        json!({
+            "severity": self.severity.name(),
+            "code": self.code,
+            "message": self.message,
+            "file": sources.name(self.span.source),
+            "span": span_json(sources, self.span),
+            "labels": self
+                .labels
+                .iter()
+                .map(|x| json!({ "message": x.message, "span": span_json(sources, x.span) }))
+                .collect::<Vec<_>>(),
+            "notes": self.notes,
+            "suggestions": self.help,
+        })
+    }
+}
+
+fn span_json(sources: &Sources, span: Span) -> Value {
+    // Where it ends is worked out from the source, if it's known.
+    let text = sources.text(span.source).unwrap_or("");
+    let (line_end, column_end) = match text.get(span.start..span.end) {
+        Some(text) => match text.rfind('\n') {
+            Some(x) => (
+                span.line + text.matches('\n').count() as u32,
+                text[x + 1..].chars().count() + 1,
+            ),
+            None => (span.line, span.column as usize + text.chars().count()),
+        },
+        None => (span.line, span.column as usize),
+    };
+
+    json!({
+        "file": sources.name(span.source),
+        "byte_start": span.start,
+        "byte_end": span.end,
+        "line_start": span.line,
+        "column_start": span.column,
+        "line_end": line_end,
+        "column_end": column_end,
+    })
+}
+
+// Every source that's been scanned, so diagnostics can show the lines they point at.
+// A SourceId is an index into it.
+#[derive(Default)]
+pub struct Sources {
+    files: Vec<(String, String)>,
+}
+
+impl Sources {
+    pub fn new() -> Sources {
+        Sources::default()
+    }
+
+    // The name is what diagnostics call it, usually its path.
+    pub fn add(&mut self, name: &str, text: &str) -> SourceId {
+        self.files.push((name.to_string(), text.to_string()));
+        SourceId(self.files.len() as u32 - 1)
+    }
+
+    pub fn name(&self, id: SourceId) -> &str {
+        match self.files.get(id.0 as usize) {
+            Some((name, _)) => name,
+            None => "<unknown>",
+        }
+    }
+
+    pub fn text(&self, id: SourceId) -> Option<&str> {
+        self.files.get(id.0 as usize).map(|(_, text)| text.as_str())
+    }
+}
+
+// Where to underline a span, in the display columns of its first line.
+struct Mark {
+    span: Span,
+    // None if the source isn't known.
+    text: Option<String>,
+    column: usize,
+    width: usize,
+    label: Option<String>,
+}
+
+impl Mark {
+    fn new(sources: &Sources, span: Span, label: Option<&String>) -> Mark {
+        let mut mark = Mark {
+            span,
+            text: None,
+            column: 0,
+            width: 1,
+            label: label.cloned(),
+        };
+
+        let source = match sources.text(span.source) {
+            Some(x) => x,
+            None => return mark,
+        };
+        let start = span.start.min(source.len());
+        if !source.is_char_boundary(start) || !source.is_char_boundary(span.end.min(source.len())) {
+            return mark;
+        }
+        let line_start = source[..start].rfind('\n').map_or(0, |x| x + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |x| start + x);
+        // Anything past the end of the first line isn't underlined.
+        let end = span.end.clamp(start, line_end);
+
+        mark.text = Some(display_width_text(&source[line_start..line_end]));
+        mark.column = display_width(&source[line_start..start]);
+        mark.width = display_width(&source[start..end]).max(1);
+        mark
+    }
+}
+
+// Tabs are shown as four spaces so the underlines line up with them.
+fn display_width_text(text: &str) -> String {
+    text.replace('\t', "    ")
+}
+
+fn display_width(text: &str) -> usize {
+    text.chars().map(|x| if x == '\t' { 4 } else { 1 }).sum()
+}
+
+pub fn report(diagnostics: &[Diagnostic], sources: &Sources) {
+    for diagnostic in diagnostics {
+        eprintln!("{}", diagnostic.render(sources));
+    }
+}
+
+// One JSON object a line, so a tool can read them as they come.
+pub fn report_json(diagnostics: &[Diagnostic], sources: &Sources) {
+    for diagnostic in diagnostics {
+        eprintln!("{}", diagnostic.to_json(sources));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LogLevel {
     Error,
     Warning,
     Info,
 }
 
-impl Display for LogLevel {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl LogLevel {
+    pub fn name(&self) -> &'static str {
         match self {
-            LogLevel::Error => f.write_str(&"error".bold().red().to_string()),
-            LogLevel::Warning => f.write_str(&"warning".bold().yellow().to_string()),
-            LogLevel::Info => f.write_str(&"info".bold().blue().to_string()),
+            LogLevel::Error => "error",
+            LogLevel::Warning => "warning",
+            LogLevel::Info => "info",
         }
     }
+
+    // In the colour the level is shown in.
+    fn paint(&self, text: &str) -> String {
+        match self {
+            LogLevel::Error => text.bold().red().to_string(),
+            LogLevel::Warning => text.bold().yellow().to_string(),
+            LogLevel::Info => text.bold().blue().to_string(),
+        }
+    }
+}
+
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.paint(self.name()))
+    }
 }
 
 pub trait Error {
-    fn report(&self, source: &str);
+    fn diagnostic(&self) -> Diagnostic;
 }
@@ -0,0 +1,10 @@
+pub mod error;
+pub mod manifest;
+pub mod parser;
+pub mod scanner;
+pub mod vm;
+
+pub use parser::clock::Clock;
+pub use parser::expr::Literal;
+pub use parser::native_object::{object, NativeObject};
+pub use vm::{EvalError, Vm, VmBuilder};
@@ -1,66 +1,252 @@
-use std::env::args;
+use horba::error::{self, Diagnostic, Error as _};
+use horba::manifest::Project;
+use horba::parser::clock::Clock;
+use horba::parser::interpreter::Interpreter;
+use horba::{parser, vm};
+use std::env::{self, args};
 use std::error::Error;
 use std::fs;
+use std::path::Path;
 use std::process::exit;
 
-pub mod error;
-pub mod parser;
-pub mod scanner;
-
 fn main() {
-    let args: Vec<String> = args().collect();
-    match args.len() {
-        x if x > 2 => println!("Usage: horba [script]"),
-        x if x == 2 => run_file(&args[1]).expect("Could not run file"),
-        _ => run_prompt(),
+    let mut args: Vec<String> = args().collect();
+
+    // Only flags before the script count, anything after it belongs to the script.
+    let flags = args.iter().skip(1).take_while(|x| x.starts_with("--")).count();
+    let flags: Vec<String> = args.drain(1..=flags).collect();
+    let mut clock = Clock::real();
+    let mut options = Options {
+        max_errors: parser::MAX_ERRORS,
+        error_format: ErrorFormat::Human,
+    };
+    let mut bad_flag = false;
+    for flag in flags.iter() {
+        match flag.split_once('=') {
+            // Timers finish instantly and in order, for testing scripts that sleep.
+            None if flag == "--virtual-clock" => clock = Clock::new_virtual(),
+            Some(("--max-errors", x)) => match x.parse() {
+                Ok(x) if x > 0 => options.max_errors = x,
+                _ => bad_flag = true,
+            },
+            Some(("--error-format", "human")) => options.error_format = ErrorFormat::Human,
+            Some(("--error-format", "json")) => options.error_format = ErrorFormat::Json,
+            _ => bad_flag = true,
+        }
+    }
+
+    let result = match args.get(1).map(String::as_str) {
+        _ if bad_flag => Err(Failure::Usage),
+        Some("run") => match project_args(&args[2..]) {
+            Some((directory, script_args)) => {
+                run_project(directory, script_args, clock, &options)
+            }
+            None => Err(Failure::Usage),
+        },
+        Some(path) => run_file(path, args[2..].to_vec(), clock, &options),
+        None => run_prompt(clock, &options),
+    };
+
+    match result {
+        Ok(()) => exit(0),
+        Err(failure) => {
+            match &failure {
+                Failure::Usage => usage(),
+                Failure::Input(e) => eprintln!("{}", e),
+                // Already reported against the script's source.
+                Failure::Syntax(_) | Failure::Runtime(_) => (),
+            }
+            exit(failure.exit_code())
+        }
+    }
+}
+
+// What the command line flags ask for.
+struct Options {
+    // Syntax errors to report before giving up on the rest of the file.
+    max_errors: usize,
+    error_format: ErrorFormat,
+}
+
+// How errors and warnings are written to stderr.
+enum ErrorFormat {
+    // Coloured and underlined, for people.
+    Human,
+    // A JSON object per line, for editors and CI.
+    Json,
+}
+
+impl Options {
+    // Against the sources the interpreter has run, each diagnostic shows its own.
+    fn report(&self, diagnostics: &[Diagnostic], interpreter: &Interpreter) {
+        let sources = interpreter.sources();
+        match self.error_format {
+            ErrorFormat::Human => error::report(diagnostics, &sources),
+            ErrorFormat::Json => error::report_json(diagnostics, &sources),
+        }
+    }
+}
+
+// Why horba didn't run a script cleanly. Each has its own exit code from sysexits.h,
+// like the book's jlox, so whatever ran it can tell them apart.
+enum Failure {
+    // The command line didn't make sense.
+    Usage,
+    // The script didn't scan or parse, so none of it ran.
+    Syntax(Vec<Diagnostic>),
+    // The script or the project couldn't be loaded.
+    Input(Box<dyn Error>),
+    // The script hit an error it didn't catch, or a spawned task did.
+    Runtime(Vec<Diagnostic>),
+}
+
+impl Failure {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Failure::Usage => 64,
+            Failure::Syntax(_) => 65,
+            Failure::Input(_) => 66,
+            Failure::Runtime(_) => 70,
+        }
+    }
+
+    fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            Failure::Syntax(x) | Failure::Runtime(x) => x,
+            _ => &[],
+        }
+    }
+}
+
+fn usage() {
+    eprintln!(
+        "Usage: horba [flags] [script [args...]]\n       horba [flags] run [directory] [-- args...]\n\nFlags:\n  --virtual-clock    Timers finish instantly, in order\n  --max-errors=N     Stop after N syntax errors (default {})\n  --error-format=F   human (default) or json, one object per line",
+        parser::MAX_ERRORS
+    )
+}
+
+// `run`'s optional directory, then the script's arguments after a `--`.
+fn project_args(args: &[String]) -> Option<(Option<&String>, Vec<String>)> {
+    let (directory, rest) = match args.first() {
+        Some(x) if x != "--" => (Some(x), &args[1..]),
+        _ => (None, args),
+    };
+
+    match rest.split_first() {
+        None => Some((directory, Vec::new())),
+        Some((separator, script_args)) if separator == "--" => {
+            Some((directory, script_args.to_vec()))
+        }
+        Some(_) => None,
+    }
+}
+
+fn run_file(
+    path: &str,
+    script_args: Vec<String>,
+    clock: Clock,
+    options: &Options,
+) -> Result<(), Failure> {
+    let interpreter = Interpreter::with_clock(clock);
+    interpreter.set_args(script_args);
+    run_script(Path::new(path), interpreter, options)
+}
+
+// `horba run` finds the horba.toml and runs its entry, with its source roots and dependencies
+// available to import.
+fn run_project(
+    directory: Option<&String>,
+    script_args: Vec<String>,
+    clock: Clock,
+    options: &Options,
+) -> Result<(), Failure> {
+    let directory = match directory {
+        Some(x) => x.into(),
+        None => env::current_dir().map_err(|e| Failure::Input(e.into()))?,
     };
+    let project = Project::find(&directory).map_err(Failure::Input)?;
+
+    let interpreter = Interpreter::with_clock(clock);
+    interpreter.set_args(script_args);
+    for root in project.package.source_roots.iter() {
+        interpreter.add_search_path(root.clone());
+    }
+    interpreter.add_package(&project.package);
+    for dependency in project.dependencies.iter() {
+        interpreter.add_package(dependency);
+    }
+
+    let entry = project.package.entry;
+    run_script(&entry, interpreter, options).map_err(|e| match e {
+        Failure::Input(e) => {
+            Failure::Input(format!("Couldn't run {}: {}", entry.display(), e).into())
+        }
+        x => x,
+    })
 }
 
-// That () in the return type should be ! but its experimental so /shrug
-// This doesn't return anything other than an Err because it exits out if it worked
-fn run_file(path: &str) -> Result<(), Box<dyn Error>> {
-    let file = fs::read_to_string(path)?;
-    match run(file) {
-        true => exit(1),
-        false => exit(0),
+fn run_script(path: &Path, interpreter: Interpreter, options: &Options) -> Result<(), Failure> {
+    let file = fs::read_to_string(path).map_err(|e| {
+        Failure::Input(format!("Couldn't read {}: {}", path.display(), e).into())
+    })?;
+    interpreter.set_script_path(path);
+    interpreter.set_max_errors(options.max_errors);
+
+    let result = run(&path.display().to_string(), &file, &interpreter, options, false);
+    match &result {
+        Ok(warnings) => options.report(warnings, &interpreter),
+        Err(failure) => options.report(failure.diagnostics(), &interpreter),
     }
+    result.map(|_| ())
 }
 
-fn run_prompt() {
+fn run_prompt(clock: Clock, options: &Options) -> Result<(), Failure> {
     let mut rl = rustyline::Editor::<()>::new();
+    // One interpreter for the whole session, so variables stick around between lines.
+    let interpreter = Interpreter::with_clock(clock);
+    interpreter.set_max_errors(options.max_errors);
     loop {
         let readline = rl.readline(">> ");
         match readline {
-            Ok(line) if line == *".exit" => exit(0),
-            Ok(line) => { run(line); },
+            Ok(line) if line == *".exit" => return Ok(()),
+            Ok(line) => match run("<prompt>", &line, &interpreter, options, true) {
+                Ok(warnings) => options.report(&warnings, &interpreter),
+                Err(failure) => options.report(failure.diagnostics(), &interpreter),
+            },
             Err(_) => println!(),
         }
     }
 }
 
-fn run(source: String) -> bool {
-    let mut scanner = scanner::scanner::Scanner::new(source.clone());
-    let tokens = scanner.scan_tokens();
+// Ok has any warnings, a Failure has them along with what stopped it.
+// With echo, like in the REPL, expression statements print their value.
+fn run(
+    name: &str,
+    source: &str,
+    interpreter: &Interpreter,
+    options: &Options,
+    echo: bool,
+) -> Result<Vec<Diagnostic>, Failure> {
+    let id = interpreter.add_source(name, source);
+    let program = parser::parse_source(source, id, options.max_errors).map_err(Failure::Syntax)?;
+    let mut diagnostics = program.warnings;
 
-    let mut exit_err = false;
-
-    /*println!();
-    for token in &tokens {
-        println!("{}", token);
+    for statement in program.statements {
+        let ran = match echo {
+            true => interpreter.interpret_line(statement),
+            false => interpreter.interpret(statement),
+        };
+        if let Err(e) = ran {
+            diagnostics.push(e.diagnostic());
+            return Err(Failure::Runtime(diagnostics));
+        }
     }
-    println!();*/
-
-    let mut parser = parser::Parser::new(source.clone(), tokens);
-    let interpreter = parser::interpreter::Interpreter {};
-
-    while let Some(expression) = parser.parse() {
-        /*println!("expr: {:?}", expression);
-        println!(
-            "astprinter: {}\n",
-            parser::ast_printer::AstPrinter {}.print(expression)
-        )*/
-        interpreter.interpret(expression, &source).unwrap_or_else(|_| { exit_err = false; () });
+
+    // Anything spawned and never awaited still gets to finish.
+    if let Err(failed) = interpreter.run_event_loop() {
+        diagnostics.extend(failed.iter().map(vm::task_diagnostic));
+        return Err(Failure::Runtime(diagnostics));
     }
 
-    exit_err
+    Ok(diagnostics)
 }
@@ -0,0 +1,178 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_NAME: &str = "horba.toml";
+
+// What's in a horba.toml. Paths in it are relative to the directory it's in.
+//
+//     [package]
+//     name = "tools"
+//     entry = "src/main.hb"
+//     source-roots = ["src"]
+//
+//     [dependencies]
+//     strings = { path = "vendor/strings" }
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub package: PackageInfo,
+    #[serde(default)]
+    pub dependencies: HashMap<String, Dependency>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct PackageInfo {
+    pub name: String,
+    #[serde(default = "default_entry")]
+    pub entry: PathBuf,
+    #[serde(default = "default_source_roots")]
+    pub source_roots: Vec<PathBuf>,
+}
+
+// Only local paths, there's no registry to get anything else from.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Dependency {
+    pub path: PathBuf,
+}
+
+fn default_entry() -> PathBuf {
+    PathBuf::from("src/main.hb")
+}
+
+fn default_source_roots() -> Vec<PathBuf> {
+    vec![PathBuf::from("src")]
+}
+
+// A package with all of its paths made absolute.
+pub struct Package {
+    pub name: String,
+    pub root: PathBuf,
+    pub entry: PathBuf,
+    pub source_roots: Vec<PathBuf>,
+    // The packages its own manifest depends on, the only ones it can import by name.
+    pub dependencies: Vec<String>,
+}
+
+// The package being run, and every package it depends on, directly or not.
+pub struct Project {
+    pub package: Package,
+    pub dependencies: Vec<Package>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Manifest, Box<dyn Error>> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("Invalid {}: {}", path.display(), e).into())
+    }
+}
+
+impl Package {
+    fn new(root: PathBuf, manifest: Manifest) -> Package {
+        let mut dependencies: Vec<String> = manifest.dependencies.into_keys().collect();
+        dependencies.sort();
+
+        let info = manifest.package;
+        Package {
+            name: info.name,
+            entry: root.join(info.entry),
+            source_roots: info.source_roots.iter().map(|x| root.join(x)).collect(),
+            root,
+            dependencies,
+        }
+    }
+}
+
+impl Project {
+    // Looks for a horba.toml in `directory`, then in each of its parents.
+    pub fn find(directory: &Path) -> Result<Project, Box<dyn Error>> {
+        let directory = fs::canonicalize(directory)
+            .map_err(|e| format!("Couldn't open {}: {}", directory.display(), e))?;
+
+        for root in directory.ancestors() {
+            if root.join(MANIFEST_NAME).is_file() {
+                return Project::load(root);
+            }
+        }
+
+        Err(format!(
+            "Couldn't find a {} in {} or any of its parents.",
+            MANIFEST_NAME,
+            directory.display()
+        )
+        .into())
+    }
+
+    pub fn load(root: &Path) -> Result<Project, Box<dyn Error>> {
+        let manifest = Manifest::load(&root.join(MANIFEST_NAME))?;
+
+        let mut dependencies = Vec::new();
+        let mut seen = HashMap::from([(manifest.package.name.clone(), root.to_path_buf())]);
+        collect_dependencies(root, &manifest, &mut seen, &mut dependencies)?;
+
+        Ok(Project {
+            package: Package::new(root.to_path_buf(), manifest),
+            dependencies,
+        })
+    }
+}
+
+// Walks the path dependencies depth first. A package can be depended on more than once,
+// as long as every name always means the same directory.
+fn collect_dependencies(
+    root: &Path,
+    manifest: &Manifest,
+    seen: &mut HashMap<String, PathBuf>,
+    dependencies: &mut Vec<Package>,
+) -> Result<(), Box<dyn Error>> {
+    let mut names: Vec<&String> = manifest.dependencies.keys().collect();
+    names.sort();
+
+    for name in names {
+        let path = root.join(&manifest.dependencies[name].path);
+        let path = fs::canonicalize(&path).map_err(|e| {
+            format!(
+                "Couldn't find dependency '{}' at {}: {}",
+                name,
+                path.display(),
+                e
+            )
+        })?;
+
+        match seen.get(name) {
+            Some(x) if *x == path => continue,
+            Some(x) => {
+                return Err(format!(
+                    "Dependency '{}' is both {} and {}.",
+                    name,
+                    x.display(),
+                    path.display()
+                )
+                .into())
+            }
+            None => seen.insert(name.clone(), path.clone()),
+        };
+
+        let dependency = Manifest::load(&path.join(MANIFEST_NAME))?;
+        if dependency.package.name != *name {
+            return Err(format!(
+                "Dependency '{}' at {} is named '{}' in its {}.",
+                name,
+                path.display(),
+                dependency.package.name,
+                MANIFEST_NAME
+            )
+            .into());
+        }
+
+        collect_dependencies(&path, &dependency, seen, dependencies)?;
+        dependencies.push(Package::new(path, dependency));
+    }
+
+    Ok(())
+}
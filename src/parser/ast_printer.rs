@@ -1,51 +1,118 @@
 use super::expr::{self, *};
-use crate::scanner::token::Token;
+use crate::scanner::token::{Span, Token};
 use crate::scanner::token_type::TokenType;
 
 #[derive(Clone, Copy)]
 pub struct AstPrinter;
 
 impl expr::ExprVisitor<String> for AstPrinter {
-    fn visit_grouping(&self, expr: &mut Grouping) -> String {
-        self.parenthesize("group", vec![&mut expr.expression])
+    fn visit_grouping(&self, expr: &Grouping) -> String {
+        self.parenthesize("group", vec![&expr.expression])
     }
 
-    fn visit_binary(&self, expr: &mut Binary) -> String {
-        self.parenthesize(&expr.operator.lexeme, vec![&mut expr.left, &mut expr.right])
+    fn visit_binary(&self, expr: &Binary) -> String {
+        self.parenthesize(&expr.operator.lexeme, vec![&expr.left, &expr.right])
     }
 
-    fn visit_literal(&self, expr: &mut Literal) -> String {
+    fn visit_literal(&self, expr: &Literal) -> String {
         match expr {
             Literal::Number(x) => x.to_string(),
             Literal::String(x) => x.to_string(),
             Literal::True => "true".to_string(),
             Literal::False => "false".to_string(),
             Literal::Null => "null".to_string(),
+            x => x.to_string(),
         }
     }
 
-    fn visit_unary(&self, expr: &mut Unary) -> String {
-        self.parenthesize(&expr.operator.lexeme, vec![&mut expr.expression])
+    fn visit_unary(&self, expr: &Unary) -> String {
+        self.parenthesize(&expr.operator.lexeme, vec![&expr.expression])
     }
 
-    fn visit_ternary(&self, expr: &mut Ternary) -> String {
+    fn visit_ternary(&self, expr: &Ternary) -> String {
         self.parenthesize(
             "ternary",
-            vec![&mut expr.condition, &mut expr.if_true, &mut expr.if_false],
+            vec![&expr.condition, &expr.if_true, &expr.if_false],
         )
     }
 
-    fn visit_comma(&self, expr: &mut Comma) -> String {
-        self.parenthesize("comma", vec![&mut expr.expr, &mut expr.next])
+    fn visit_comma(&self, expr: &Comma) -> String {
+        self.parenthesize("comma", vec![&expr.expr, &expr.next])
+    }
+
+    fn visit_variable(&self, expr: &Variable) -> String {
+        expr.name.lexeme.clone()
+    }
+
+    fn visit_call(&self, expr: &Call) -> String {
+        let mut string = self.parenthesize("call", vec![&expr.callee]);
+        string.pop();
+        for argument in expr.arguments.iter() {
+            string.push(' ');
+            string.push_str(&argument.accept(Box::new(*self)));
+        }
+        string.push(')');
+        string
+    }
+
+    fn visit_get(&self, expr: &Get) -> String {
+        self.parenthesize(&format!(".{}", expr.name.lexeme), vec![&expr.object])
+    }
+
+    fn visit_assign(&self, expr: &Assign) -> String {
+        let mut string = String::from("(=");
+        for target in expr.targets.iter().chain(expr.values.iter()) {
+            string.push(' ');
+            string.push_str(&target.accept(Box::new(*self)));
+        }
+        string.push(')');
+        string
+    }
+
+    fn visit_array(&self, expr: &Array) -> String {
+        let mut string = String::from("(array");
+        for element in expr.elements.iter() {
+            string.push(' ');
+            string.push_str(&element.accept(Box::new(*self)));
+        }
+        string.push(')');
+        string
+    }
+
+    fn visit_index(&self, expr: &Index) -> String {
+        self.parenthesize("index", vec![&expr.object, &expr.index])
+    }
+
+    fn visit_propagate(&self, expr: &Propagate) -> String {
+        self.parenthesize("?", vec![&expr.expression])
+    }
+
+    fn visit_await(&self, expr: &Await) -> String {
+        self.parenthesize("await", vec![&expr.value])
+    }
+
+    fn visit_match(&self, expr: &Match) -> String {
+        let mut string = self.parenthesize("match", vec![&expr.value]);
+        string.pop();
+        for arm in expr.arms.iter() {
+            let name = match &arm.guard {
+                Some(guard) => format!("{} if {}", arm.pattern, guard.accept(Box::new(*self))),
+                None => arm.pattern.to_string(),
+            };
+            string.push(' ');
+            string.push_str(&self.parenthesize(&name, vec![&arm.body]));
+        }
+        string.push(')');
+        string
     }
 }
 
 impl AstPrinter {
-    pub fn print(&self, mut expr: Expr) -> String {
+    pub fn print(&self, expr: Expr) -> String {
         expr.accept(Box::new(*self))
     }
 
-    fn parenthesize(&self, name: &str, exprs: Vec<&mut Box<Expr>>) -> String {
+    fn parenthesize(&self, name: &str, exprs: Vec<&Expr>) -> String {
         let mut string = String::new();
 
         string.push('(');
@@ -61,25 +128,19 @@ impl AstPrinter {
 }
 
 pub fn ast_test() {
+    let span = Span::default();
     let expr = Expr::Binary(Binary {
         left: Box::new(Expr::Unary(Unary {
-            operator: Token {
-                token: TokenType::Minus,
-                lexeme: "-".to_string(),
-                line: 1,
-                column: 1,
-            },
-            expression: Box::new(Expr::Literal(Literal::Number(123.0))),
+            operator: Token::new(TokenType::Minus, "-".to_string(), span),
+            expression: Box::new(Expr::Literal(Literal::Number(123.0), span)),
+            span,
         })),
-        operator: Token {
-            token: TokenType::Star,
-            lexeme: "*".to_string(),
-            line: 1,
-            column: 1,
-        },
+        operator: Token::new(TokenType::Star, "*".to_string(), span),
         right: Box::new(Expr::Grouping(Grouping {
-            expression: Box::new(Expr::Literal(Literal::Number(45.67))),
+            expression: Box::new(Expr::Literal(Literal::Number(45.67), span)),
+            span,
         })),
+        span,
     });
     println!("{}", AstPrinter {}.print(expr));
 }
@@ -0,0 +1,54 @@
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// The virtual clock's wall time starts at the Unix epoch, so dates in tests never change.
+const VIRTUAL_EPOCH: f64 = 0.0;
+
+// Where the event loop gets the time from, in milliseconds since the interpreter started.
+// The virtual clock never really waits, it jumps straight to the next timer,
+// so scripts using it run instantly and always in the same order.
+pub enum Clock {
+    Real(Instant),
+    Virtual(f64),
+}
+
+impl Clock {
+    pub fn real() -> Clock {
+        Clock::Real(Instant::now())
+    }
+
+    pub fn new_virtual() -> Clock {
+        Clock::Virtual(0.0)
+    }
+
+    pub fn now(&self) -> f64 {
+        match self {
+            Clock::Real(start) => start.elapsed().as_secs_f64() * 1000.0,
+            Clock::Virtual(now) => *now,
+        }
+    }
+
+    // Milliseconds since the Unix epoch, for dates rather than measuring how long things take.
+    pub fn wall_time(&self) -> f64 {
+        match self {
+            Clock::Real(_) => match SystemTime::now().duration_since(UNIX_EPOCH) {
+                Ok(x) => x.as_secs_f64() * 1000.0,
+                Err(e) => -e.duration().as_secs_f64() * 1000.0,
+            },
+            Clock::Virtual(now) => VIRTUAL_EPOCH + now,
+        }
+    }
+
+    // Blocks until `time`, or just moves the time forward for the virtual clock.
+    pub fn wait_until(&mut self, time: f64) {
+        match self {
+            Clock::Real(_) => {
+                let remaining = time - self.now();
+                if remaining > 0.0 {
+                    thread::sleep(Duration::from_secs_f64(remaining / 1000.0));
+                }
+            }
+            Clock::Virtual(now) => *now = now.max(time),
+        }
+    }
+}
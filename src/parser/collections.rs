@@ -0,0 +1,448 @@
+use super::enumeration::VariantType;
+use super::expr::Literal;
+use super::interpreter::type_error;
+use super::native::NativeFunction;
+use super::runtime_error::{RuntimeError, Unwind};
+use crate::scanner::token::Token;
+use indexmap::IndexMap;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use std::fmt;
+use std::rc::Rc;
+use std::sync::atomic::{self, AtomicU64};
+
+// Keeps the order things were added in, so printing and looping over a set is predictable.
+//
+// Membership goes by `==`, and NaN isn't `==` to anything. So every NaN added is a new element,
+// has(NaN) and remove(NaN) are always False, and a set NaNs keep being added to keeps growing.
+// The only way to get them out again is clear(), or to make a new set without them.
+pub struct Set {
+    elements: IndexMap<Key, Literal>,
+}
+
+#[derive(PartialEq)]
+pub struct Deque {
+    elements: VecDeque<Literal>,
+}
+
+// Pops the lowest priority first, and things with the same priority in the order they were pushed.
+pub struct PriorityQueue {
+    heap: BinaryHeap<Entry>,
+    pushed: u64,
+}
+
+struct Entry {
+    priority: f64,
+    order: u64,
+    value: Literal,
+}
+
+// What a set hashes a value as. Two values get the same key exactly when `==` says they're
+// equal: 0 and -0 are the same number, and NaN isn't equal to anything, itself included,
+// so every NaN gets a key of its own that nothing else will ever match, see Set.
+#[derive(PartialEq, Eq, Hash, Clone)]
+enum Key {
+    Number(u64),
+    NaN(u64),
+    String(String),
+    True,
+    False,
+    Null,
+    Variant(String, String, Vec<Key>),
+    // Structs, enums and variant types are equal when they're declared the same way, so these
+    // are their names and fields.
+    Struct(String, Vec<String>),
+    Enum(String, Vec<Key>),
+    VariantType(String, String, Vec<String>),
+    Function(usize, usize),
+    // Everything else is only ever equal to itself.
+    Identity(usize),
+}
+
+static NAN_KEYS: AtomicU64 = AtomicU64::new(0);
+
+impl Key {
+    // Arrays, instances, sets and deques are left out. They're `==` by what's in them, so changing
+    // one after it was added would lose it in the set.
+    fn new(value: &Literal, token: &Token) -> Result<Key, Unwind> {
+        Ok(match value {
+            Literal::Number(x) if x.is_nan() => {
+                Key::NaN(NAN_KEYS.fetch_add(1, atomic::Ordering::Relaxed))
+            }
+            // Adding 0.0 turns -0 into 0.
+            Literal::Number(x) => Key::Number((x + 0.0).to_bits()),
+            Literal::String(x) => Key::String(x.clone()),
+            Literal::True => Key::True,
+            Literal::False => Key::False,
+            Literal::Null => Key::Null,
+            Literal::Variant(x) => {
+                let mut values = Vec::new();
+                for value in x.values.iter() {
+                    values.push(Key::new(value, token)?);
+                }
+                Key::Variant(
+                    x.variant_type.enum_name.clone(),
+                    x.variant_type.name.clone(),
+                    values,
+                )
+            }
+            Literal::Struct(x) => Key::Struct(x.name.clone(), x.fields.clone()),
+            Literal::Enum(x) => Key::Enum(
+                x.name.clone(),
+                x.variants.iter().map(|x| Key::variant_type(x)).collect(),
+            ),
+            Literal::VariantType(x) => Key::variant_type(x),
+            Literal::Function(x) => Key::Function(
+                Rc::as_ptr(&x.declaration) as usize,
+                Rc::as_ptr(&x.closure) as usize,
+            ),
+            Literal::Native(x) => Key::Identity(Rc::as_ptr(x) as *const () as usize),
+            Literal::Generator(x) => Key::Identity(Rc::as_ptr(x) as *const () as usize),
+            Literal::Task(x) => Key::Identity(Rc::as_ptr(x) as *const () as usize),
+            Literal::Module(x) => Key::Identity(Rc::as_ptr(x) as *const () as usize),
+            Literal::Regex(x) => Key::Identity(Rc::as_ptr(x) as *const () as usize),
+            Literal::PriorityQueue(x) => Key::Identity(Rc::as_ptr(x) as *const () as usize),
+            Literal::Object(x) => Key::Identity(Rc::as_ptr(x) as *const () as usize),
+            x
+            @ (Literal::Array(_) | Literal::Instance(_) | Literal::Set(_) | Literal::Deque(_)) => {
+                return Err(RuntimeError::new(
+                    token,
+                    format!(
+                        "{} can't go in a Set, it could change after it's added.",
+                        Literal::type_name(x)
+                    ),
+                )
+                .with_code("E0202")
+                .into())
+            }
+        })
+    }
+
+    fn variant_type(variant_type: &VariantType) -> Key {
+        Key::VariantType(
+            variant_type.enum_name.clone(),
+            variant_type.name.clone(),
+            variant_type.fields.clone(),
+        )
+    }
+}
+
+// Set(), Deque() and PriorityQueue() make empty ones, Set and Deque can also start from an array.
+pub fn constructors() -> Vec<NativeFunction> {
+    vec![
+        NativeFunction::new("Set", 0, |_, arguments, paren| {
+            let mut set = Set {
+                elements: IndexMap::new(),
+            };
+            for value in initial(&arguments[0], paren)? {
+                set.elements
+                    .entry(Key::new(&value, paren)?)
+                    .or_insert(value);
+            }
+            Ok(Literal::Set(Rc::new(RefCell::new(set))))
+        })
+        .with_optional(1),
+        NativeFunction::new("Deque", 0, |_, arguments, paren| {
+            let deque = Deque {
+                elements: initial(&arguments[0], paren)?.into(),
+            };
+            Ok(Literal::Deque(Rc::new(RefCell::new(deque))))
+        })
+        .with_optional(1),
+        NativeFunction::new("PriorityQueue", 0, |_, _, _| {
+            let queue = PriorityQueue {
+                heap: BinaryHeap::new(),
+                pushed: 0,
+            };
+            Ok(Literal::PriorityQueue(Rc::new(RefCell::new(queue))))
+        }),
+    ]
+}
+
+fn initial(value: &Literal, paren: &Token) -> Result<Vec<Literal>, Unwind> {
+    match value {
+        Literal::Null => Ok(Vec::new()),
+        Literal::Array(x) => Ok(x.borrow().clone()),
+        x => Err(type_error(paren, "Array", x).into()),
+    }
+}
+
+type Method<T> = fn(&Rc<RefCell<T>>, &[Literal], &Token) -> Result<Literal, Unwind>;
+
+// A native function with the collection it was got from already filled in.
+fn method<T: 'static>(target: &Rc<RefCell<T>>, name: &str, arity: usize, f: Method<T>) -> Literal {
+    let target = target.clone();
+    Literal::Native(Rc::new(NativeFunction::new(
+        name,
+        arity,
+        move |_, arguments, paren| f(&target, &arguments, paren),
+    )))
+}
+
+impl Set {
+    pub fn get(set: &Rc<RefCell<Set>>, name: &Token) -> Option<Literal> {
+        let name = name.lexeme.as_str();
+        Some(match name {
+            "size" => Literal::Number(set.borrow().elements.len() as f64),
+            "add" => method(set, name, 1, |set, arguments, paren| {
+                let key = Key::new(&arguments[0], paren)?;
+                // Something equal that's already there stays, like 0 when adding -0.
+                set.borrow_mut()
+                    .elements
+                    .entry(key)
+                    .or_insert_with(|| arguments[0].clone());
+                Ok(Literal::Null)
+            }),
+            "has" => method(set, name, 1, |set, arguments, paren| {
+                let key = Key::new(&arguments[0], paren)?;
+                Ok(boolean(set.borrow().elements.contains_key(&key)))
+            }),
+            // True if it was in the set.
+            "remove" => method(set, name, 1, |set, arguments, paren| {
+                let key = Key::new(&arguments[0], paren)?;
+                let removed = set.borrow_mut().elements.shift_remove(&key);
+                Ok(boolean(removed.is_some()))
+            }),
+            "clear" => method(set, name, 0, |set, _, _| {
+                set.borrow_mut().elements.clear();
+                Ok(Literal::Null)
+            }),
+            "to_array" => method(set, name, 0, |set, _, _| Ok(array(set.borrow().values()))),
+            "union" => method(set, name, 1, |set, arguments, paren| {
+                let mut elements = set.borrow().elements.clone();
+                for (key, value) in Set::other(&arguments[0], paren)?.borrow().elements.iter() {
+                    elements.entry(key.clone()).or_insert_with(|| value.clone());
+                }
+                Ok(Set::literal(elements))
+            }),
+            "intersection" => method(set, name, 1, |set, arguments, paren| {
+                let other = Set::other(&arguments[0], paren)?;
+                let other = other.borrow();
+                let mut elements = set.borrow().elements.clone();
+                elements.retain(|key, _| other.elements.contains_key(key));
+                Ok(Set::literal(elements))
+            }),
+            "difference" => method(set, name, 1, |set, arguments, paren| {
+                let other = Set::other(&arguments[0], paren)?;
+                let other = other.borrow();
+                let mut elements = set.borrow().elements.clone();
+                elements.retain(|key, _| !other.elements.contains_key(key));
+                Ok(Set::literal(elements))
+            }),
+            _ => return None,
+        })
+    }
+
+    pub fn values(&self) -> Vec<Literal> {
+        self.elements.values().cloned().collect()
+    }
+
+    fn other(value: &Literal, paren: &Token) -> Result<Rc<RefCell<Set>>, Unwind> {
+        match value {
+            Literal::Set(x) => Ok(x.clone()),
+            x => Err(type_error(paren, "Set", x).into()),
+        }
+    }
+
+    fn literal(elements: IndexMap<Key, Literal>) -> Literal {
+        Literal::Set(Rc::new(RefCell::new(Set { elements })))
+    }
+}
+
+impl Deque {
+    pub fn get(deque: &Rc<RefCell<Deque>>, name: &Token) -> Option<Literal> {
+        let name = name.lexeme.as_str();
+        Some(match name {
+            "size" => Literal::Number(deque.borrow().elements.len() as f64),
+            "push_back" => method(deque, name, 1, |deque, arguments, _| {
+                deque.borrow_mut().elements.push_back(arguments[0].clone());
+                Ok(Literal::Null)
+            }),
+            "push_front" => method(deque, name, 1, |deque, arguments, _| {
+                deque.borrow_mut().elements.push_front(arguments[0].clone());
+                Ok(Literal::Null)
+            }),
+            "pop_back" => method(deque, name, 0, |deque, _, paren| {
+                let value = deque.borrow_mut().elements.pop_back();
+                value.ok_or_else(|| empty("Deque", paren))
+            }),
+            "pop_front" => method(deque, name, 0, |deque, _, paren| {
+                let value = deque.borrow_mut().elements.pop_front();
+                value.ok_or_else(|| empty("Deque", paren))
+            }),
+            "peek_back" => method(deque, name, 0, |deque, _, paren| {
+                let value = deque.borrow().elements.back().cloned();
+                value.ok_or_else(|| empty("Deque", paren))
+            }),
+            "peek_front" => method(deque, name, 0, |deque, _, paren| {
+                let value = deque.borrow().elements.front().cloned();
+                value.ok_or_else(|| empty("Deque", paren))
+            }),
+            "clear" => method(deque, name, 0, |deque, _, _| {
+                deque.borrow_mut().elements.clear();
+                Ok(Literal::Null)
+            }),
+            "to_array" => method(deque, name, 0, |deque, _, _| {
+                Ok(array(deque.borrow().values()))
+            }),
+            _ => return None,
+        })
+    }
+
+    pub fn values(&self) -> Vec<Literal> {
+        self.elements.iter().cloned().collect()
+    }
+}
+
+impl PriorityQueue {
+    pub fn get(queue: &Rc<RefCell<PriorityQueue>>, name: &Token) -> Option<Literal> {
+        let name = name.lexeme.as_str();
+        Some(match name {
+            "size" => Literal::Number(queue.borrow().heap.len() as f64),
+            "push" => method(queue, name, 2, |queue, arguments, paren| {
+                let priority = match &arguments[1] {
+                    Literal::Number(x) if !x.is_nan() => *x,
+                    Literal::Number(_) => {
+                        return Err(RuntimeError::new(paren, "A priority can't be NaN.")
+                            .with_code("E0211")
+                            .into())
+                    }
+                    x => return Err(type_error(paren, "Number", x).into()),
+                };
+
+                let mut queue = queue.borrow_mut();
+                let order = queue.pushed;
+                queue.pushed += 1;
+                queue.heap.push(Entry {
+                    priority,
+                    order,
+                    value: arguments[0].clone(),
+                });
+                Ok(Literal::Null)
+            }),
+            "pop" => method(queue, name, 0, |queue, _, paren| {
+                let entry = queue.borrow_mut().heap.pop();
+                entry
+                    .map(|x| x.value)
+                    .ok_or_else(|| empty("PriorityQueue", paren))
+            }),
+            "peek" => method(queue, name, 0, |queue, _, paren| {
+                let value = queue.borrow().heap.peek().map(|x| x.value.clone());
+                value.ok_or_else(|| empty("PriorityQueue", paren))
+            }),
+            "clear" => method(queue, name, 0, |queue, _, _| {
+                queue.borrow_mut().heap.clear();
+                Ok(Literal::Null)
+            }),
+            "to_array" => method(queue, name, 0, |queue, _, _| {
+                Ok(array(queue.borrow().values()))
+            }),
+            _ => return None,
+        })
+    }
+
+    // In the order they'd be popped.
+    pub fn values(&self) -> Vec<Literal> {
+        let mut entries: Vec<&Entry> = self.heap.iter().collect();
+        entries.sort_by(|a, b| b.cmp(a));
+        entries.into_iter().map(|x| x.value.clone()).collect()
+    }
+}
+
+// BinaryHeap pops the greatest, so the lowest priority, then the earliest pushed, is greatest.
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .total_cmp(&self.priority)
+            .then(other.order.cmp(&self.order))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+fn array(values: Vec<Literal>) -> Literal {
+    Literal::Array(Rc::new(RefCell::new(values)))
+}
+
+fn boolean(value: bool) -> Literal {
+    match value {
+        true => Literal::True,
+        false => Literal::False,
+    }
+}
+
+fn empty(name: &str, paren: &Token) -> Unwind {
+    RuntimeError::new(paren, format!("The {} is empty.", name))
+        .with_code("E0211")
+        .into()
+}
+
+// Sets are equal when they have the same elements, whatever order they were added in.
+impl PartialEq for Set {
+    fn eq(&self, other: &Self) -> bool {
+        self.elements.len() == other.elements.len()
+            && self.elements.keys().all(|x| other.elements.contains_key(x))
+    }
+}
+
+// Priority queues are only ever equal to themselves.
+impl PartialEq for PriorityQueue {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for Set {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Debug for Deque {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Debug for PriorityQueue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for Set {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Set {{{}}}", join(self.values()))
+    }
+}
+
+impl fmt::Display for Deque {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Deque [{}]", join(self.values()))
+    }
+}
+
+impl fmt::Display for PriorityQueue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PriorityQueue [{}]", join(self.values()))
+    }
+}
+
+fn join(values: Vec<Literal>) -> String {
+    let values: Vec<String> = values.iter().map(|x| x.to_string()).collect();
+    values.join(", ")
+}
@@ -0,0 +1,111 @@
+use super::expr::Literal;
+use std::fmt;
+use std::rc::Rc;
+
+// The runtime side of an `enum` declaration. Variants are looked up on it with a Dot.
+#[derive(Debug, PartialEq)]
+pub struct EnumType {
+    pub name: String,
+    pub variants: Vec<Rc<VariantType>>,
+}
+
+// Calling a VariantType with its payload builds a Variant.
+// Variants without a payload skip the call and are values on their own.
+#[derive(Debug, PartialEq)]
+pub struct VariantType {
+    pub enum_name: String,
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Variant {
+    pub variant_type: Rc<VariantType>,
+    pub values: Vec<Literal>,
+}
+
+impl EnumType {
+    pub fn new(name: &str, variants: Vec<(String, Vec<String>)>) -> EnumType {
+        EnumType {
+            name: name.to_string(),
+            variants: variants
+                .into_iter()
+                .map(|(variant, fields)| {
+                    Rc::new(VariantType {
+                        enum_name: name.to_string(),
+                        name: variant,
+                        fields,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    // The built in `enum Result { Ok(value), Err(error) }` that `?` works with.
+    pub fn result() -> EnumType {
+        EnumType::new(
+            "Result",
+            vec![
+                ("Ok".to_string(), vec!["value".to_string()]),
+                ("Err".to_string(), vec!["error".to_string()]),
+            ],
+        )
+    }
+
+    pub fn get(&self, name: &str) -> Option<Literal> {
+        let variant_type = self.variants.iter().find(|x| x.name == name)?.clone();
+
+        match variant_type.fields.is_empty() {
+            true => Some(Literal::Variant(Rc::new(Variant {
+                variant_type,
+                values: Vec::new(),
+            }))),
+            false => Some(Literal::VariantType(variant_type)),
+        }
+    }
+}
+
+impl Variant {
+    // Whether it's that variant of that very enum, not just one with the same names.
+    pub fn is_variant_of(&self, enumeration: &EnumType, variant: &str) -> bool {
+        self.variant_type.name == variant
+            && enumeration
+                .variants
+                .iter()
+                .any(|x| Rc::ptr_eq(x, &self.variant_type))
+    }
+
+    pub fn get(&self, name: &str) -> Option<Literal> {
+        let index = self.variant_type.fields.iter().position(|x| x == name)?;
+        Some(self.values[index].clone())
+    }
+}
+
+impl fmt::Display for EnumType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<enum {}>", self.name)
+    }
+}
+
+impl fmt::Display for VariantType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<variant {}.{}>", self.enum_name, self.name)
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}",
+            self.variant_type.enum_name, self.variant_type.name
+        )?;
+
+        if !self.values.is_empty() {
+            let values: Vec<String> = self.values.iter().map(|x| x.to_string()).collect();
+            write!(f, "({})", values.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
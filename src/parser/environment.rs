@@ -0,0 +1,66 @@
+use super::expr::Literal;
+use super::runtime_error::{RuntimeError, Unwind};
+use crate::scanner::token::Token;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Debug)]
+pub struct Environment {
+    values: HashMap<String, Literal>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new(enclosing: Option<Rc<RefCell<Environment>>>) -> Environment {
+        Environment {
+            values: HashMap::new(),
+            enclosing,
+        }
+    }
+
+    pub fn define(&mut self, name: String, value: Literal) {
+        self.values.insert(name, value);
+    }
+
+    pub fn assign(&mut self, name: &Token, value: Literal) -> Result<(), Unwind> {
+        match self.values.get_mut(&name.lexeme) {
+            Some(x) => {
+                *x = value;
+                Ok(())
+            }
+            None => match &self.enclosing {
+                Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+                None => Err(RuntimeError::new(
+                    name,
+                    format!("Undefined variable '{}'.", name.lexeme),
+                )
+                .with_code("E0203")
+                .into()),
+            },
+        }
+    }
+
+    pub fn get(&self, name: &Token) -> Result<Literal, Unwind> {
+        match self.values.get(&name.lexeme) {
+            Some(x) => Ok(x.clone()),
+            None => match &self.enclosing {
+                Some(enclosing) => enclosing.borrow().get(name),
+                None => Err(RuntimeError::new(
+                    name,
+                    format!("Undefined variable '{}'.", name.lexeme),
+                )
+                .with_code("E0203")
+                .into()),
+            },
+        }
+    }
+
+    // Like get, for when there's no token to report an error against.
+    pub fn lookup(&self, name: &str) -> Option<Literal> {
+        match self.values.get(name) {
+            Some(x) => Some(x.clone()),
+            None => self.enclosing.as_ref()?.borrow().lookup(name),
+        }
+    }
+}
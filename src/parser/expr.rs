@@ -1,39 +1,100 @@
-use crate::scanner::token::Token;
+use super::collections::{Deque, PriorityQueue, Set};
+use super::enumeration::{EnumType, Variant, VariantType};
+use super::function::Function;
+use super::generator::Generator;
+use super::instance::{Instance, StructType};
+use super::module::Module;
+use super::native::NativeFunction;
+use super::native_object::NativeObject;
+use super::stdlib::regex::Regex;
+use super::task::Task;
+use super::pattern::Pattern;
+use crate::scanner::token::{Span, Token};
+use std::cell::RefCell;
 use std::fmt;
+use std::rc::Rc;
 
 pub trait ExprVisitor<T> {
-    fn visit_expr(&mut self, expr: &mut Expr) -> T {
+    fn visit_expr(&mut self, expr: &Expr) -> T {
         match expr {
             Expr::Grouping(x) => self.visit_grouping(x),
             Expr::Binary(x) => self.visit_binary(x),
-            Expr::Literal(x) => self.visit_literal(x),
+            Expr::Literal(x, _) => self.visit_literal(x),
             Expr::Unary(x) => self.visit_unary(x),
             Expr::Ternary(x) => self.visit_ternary(x),
             Expr::Comma(x) => self.visit_comma(x),
+            Expr::Variable(x) => self.visit_variable(x),
+            Expr::Call(x) => self.visit_call(x),
+            Expr::Get(x) => self.visit_get(x),
+            Expr::Match(x) => self.visit_match(x),
+            Expr::Assign(x) => self.visit_assign(x),
+            Expr::Array(x) => self.visit_array(x),
+            Expr::Index(x) => self.visit_index(x),
+            Expr::Propagate(x) => self.visit_propagate(x),
+            Expr::Await(x) => self.visit_await(x),
         }
     }
-    fn visit_grouping(&self, grouping: &mut Grouping) -> T;
-    fn visit_binary(&self, binary: &mut Binary) -> T;
-    fn visit_literal(&self, literal: &mut Literal) -> T;
-    fn visit_unary(&self, unary: &mut Unary) -> T;
-    fn visit_ternary(&self, ternary: &mut Ternary) -> T;
-    fn visit_comma(&self, comma: &mut Comma) -> T;
+    fn visit_grouping(&self, grouping: &Grouping) -> T;
+    fn visit_binary(&self, binary: &Binary) -> T;
+    fn visit_literal(&self, literal: &Literal) -> T;
+    fn visit_unary(&self, unary: &Unary) -> T;
+    fn visit_ternary(&self, ternary: &Ternary) -> T;
+    fn visit_comma(&self, comma: &Comma) -> T;
+    fn visit_variable(&self, variable: &Variable) -> T;
+    fn visit_call(&self, call: &Call) -> T;
+    fn visit_get(&self, get: &Get) -> T;
+    fn visit_match(&self, match_expr: &Match) -> T;
+    fn visit_assign(&self, assign: &Assign) -> T;
+    fn visit_array(&self, array: &Array) -> T;
+    fn visit_index(&self, index: &Index) -> T;
+    fn visit_propagate(&self, propagate: &Propagate) -> T;
+    fn visit_await(&self, await_expr: &Await) -> T;
 }
 
 #[derive(Debug)]
 pub enum Expr {
     Grouping(Grouping),
     Binary(Binary),
-    Literal(Literal),
+    Literal(Literal, Span),
     Unary(Unary),
     Ternary(Ternary),
     Comma(Comma),
+    Variable(Variable),
+    Call(Call),
+    Get(Get),
+    Match(Match),
+    Assign(Assign),
+    Array(Array),
+    Index(Index),
+    Propagate(Propagate),
+    Await(Await),
 }
 
 impl Expr {
-    pub fn accept<T>(&mut self, mut visitor: Box<dyn ExprVisitor<T>>) -> T {
+    pub fn accept<T>(&self, mut visitor: Box<dyn ExprVisitor<T>>) -> T {
         visitor.visit_expr(self)
     }
+
+    // All of the source the expression was parsed from.
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Grouping(x) => x.span,
+            Expr::Binary(x) => x.span,
+            Expr::Literal(_, x) => *x,
+            Expr::Unary(x) => x.span,
+            Expr::Ternary(x) => x.span,
+            Expr::Comma(x) => x.span,
+            Expr::Variable(x) => x.span,
+            Expr::Call(x) => x.span,
+            Expr::Get(x) => x.span,
+            Expr::Match(x) => x.span,
+            Expr::Assign(x) => x.span,
+            Expr::Array(x) => x.span,
+            Expr::Index(x) => x.span,
+            Expr::Propagate(x) => x.span,
+            Expr::Await(x) => x.span,
+        }
+    }
 }
 
 // Expressions
@@ -48,16 +109,29 @@ pub enum Literal {
     True,
     False,
     Null,
+    Array(Rc<RefCell<Vec<Literal>>>),
+    Struct(Rc<StructType>),
+    Instance(Rc<RefCell<Instance>>),
+    Enum(Rc<EnumType>),
+    VariantType(Rc<VariantType>),
+    Variant(Rc<Variant>),
+    Function(Rc<Function>),
+    Native(Rc<NativeFunction>),
+    Generator(Rc<RefCell<Generator>>),
+    Task(Rc<RefCell<Task>>),
+    Module(Rc<Module>),
+    Regex(Rc<Regex>),
+    Set(Rc<RefCell<Set>>),
+    Deque(Rc<RefCell<Deque>>),
+    PriorityQueue(Rc<RefCell<PriorityQueue>>),
+    Object(Rc<RefCell<dyn NativeObject>>),
 }
 
 impl Literal {
     pub fn is_truthy(expr: &Literal) -> bool {
         use Literal::*;
 
-        match expr {
-            False | Null => false,
-            _ => true,
-        }
+        !matches!(expr, False | Null)
     }
 
     pub fn to_bool(expr: &Literal) -> Literal {
@@ -85,6 +159,21 @@ impl Literal {
             String(_) => "String".to_string(),
             True | False => "Bool".to_string(), 
             Null => "Null".to_string(),
+            Array(_) => "Array".to_string(),
+            Struct(_) => "Struct".to_string(),
+            Instance(x) => x.borrow().structure.name.clone(),
+            Enum(_) => "Enum".to_string(),
+            VariantType(_) => "Variant".to_string(),
+            Variant(x) => x.variant_type.enum_name.clone(),
+            Function(_) | Native(_) => "Function".to_string(),
+            Generator(_) => "Generator".to_string(),
+            Task(_) => "Task".to_string(),
+            Module(_) => "Module".to_string(),
+            Regex(_) => "Regex".to_string(),
+            Set(_) => "Set".to_string(),
+            Deque(_) => "Deque".to_string(),
+            PriorityQueue(_) => "PriorityQueue".to_string(),
+            Object(x) => x.borrow().type_name(),
         }
     }
 }
@@ -102,6 +191,29 @@ impl fmt::Display for Literal {
                 True => "True".to_string(),
                 False => "False".to_string(),
                 Null => "Null".to_string(),
+                Array(x) => format!(
+                    "[{}]",
+                    x.borrow()
+                        .iter()
+                        .map(|x| x.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                Struct(x) => x.to_string(),
+                Instance(x) => x.borrow().to_string(),
+                Enum(x) => x.to_string(),
+                VariantType(x) => x.to_string(),
+                Variant(x) => x.to_string(),
+                Function(x) => x.to_string(),
+                Native(x) => x.to_string(),
+                Generator(x) => x.borrow().to_string(),
+                Task(x) => x.borrow().to_string(),
+                Module(x) => x.to_string(),
+                Regex(x) => x.to_string(),
+                Set(x) => x.borrow().to_string(),
+                Deque(x) => x.borrow().to_string(),
+                PriorityQueue(x) => x.borrow().to_string(),
+                Object(x) => x.borrow().to_string(),
             }
         )
     }
@@ -110,12 +222,14 @@ impl fmt::Display for Literal {
 #[derive(Debug)]
 pub struct Grouping {
     pub expression: Box<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
 pub struct Unary {
     pub operator: Token,
     pub expression: Box<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
@@ -123,6 +237,7 @@ pub struct Binary {
     pub left: Box<Expr>,
     pub operator: Token,
     pub right: Box<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
@@ -130,12 +245,90 @@ pub struct Ternary {
     pub condition: Box<Expr>,
     pub if_true: Box<Expr>,
     pub if_false: Box<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug)]
 pub struct Comma {
     pub expr: Box<Expr>,
     pub next: Box<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Variable {
+    pub name: Token,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Call {
+    pub callee: Box<Expr>,
+    pub paren: Token,
+    pub arguments: Vec<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Get {
+    pub object: Box<Expr>,
+    pub name: Token,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Match {
+    pub keyword: Token,
+    pub value: Box<Expr>,
+    pub arms: Vec<MatchArm>,
+    pub span: Span,
+}
+
+// Targets are checked by the parser to be variables, properties or indexes.
+// `a, b = b, a` evaluates every value before assigning any of them.
+#[derive(Debug)]
+pub struct Assign {
+    pub targets: Vec<Expr>,
+    pub equals: Token,
+    pub values: Vec<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Array {
+    pub elements: Vec<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Index {
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub index: Box<Expr>,
+    pub span: Span,
+}
+
+// Postfix `?`, unwraps an Ok or returns the Err from the enclosing function.
+#[derive(Debug)]
+pub struct Propagate {
+    pub expression: Box<Expr>,
+    pub question: Token,
+    pub span: Span,
+}
+
+// Only ever the whole of an expression statement, a let's value, a return value or what's printed.
+#[derive(Debug)]
+pub struct Await {
+    pub keyword: Token,
+    pub value: Box<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Box<Expr>>,
+    pub body: Box<Expr>,
 }
 
 // Operators
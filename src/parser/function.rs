@@ -0,0 +1,32 @@
+use super::environment::Environment;
+use super::stmt;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+// The declaration is shared with the AST, the closure is the scope the `fn` was declared in.
+pub struct Function {
+    pub declaration: Rc<stmt::Function>,
+    pub closure: Rc<RefCell<Environment>>,
+}
+
+// Two functions are only equal if they are the same function, from the same scope.
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.declaration, &other.declaration)
+            && Rc::ptr_eq(&self.closure, &other.closure)
+    }
+}
+
+// Not derived, the closure can contain the function itself.
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<fn {}>", self.declaration.name.lexeme)
+    }
+}
@@ -0,0 +1,160 @@
+use super::expr::Literal;
+use super::function::Function;
+use super::interpreter::Interpreter;
+use super::resume::{Flow, Frame};
+use super::runtime_error::{RuntimeError, Unwind};
+use crate::scanner::token::Token;
+use std::cell::RefCell;
+use std::fmt;
+use std::mem;
+use std::rc::Rc;
+
+// The body runs a bit at a time, see resume.rs for how it keeps its place in between.
+pub struct Generator {
+    function: Rc<Function>,
+    // The arguments, until the first resume binds them.
+    arguments: Vec<(String, Literal)>,
+    frames: Vec<Frame>,
+    state: State,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Suspended,
+    Running,
+    Done,
+}
+
+// Something a for loop can go through. Arrays are read as they go, so pushing in the loop is seen.
+pub enum Iteration {
+    Array(Rc<RefCell<Vec<Literal>>>, usize),
+    Generator(Rc<RefCell<Generator>>),
+}
+
+impl Iteration {
+    pub fn new(value: Literal, keyword: &Token) -> Result<Iteration, Unwind> {
+        match value {
+            Literal::Array(x) => Ok(Iteration::Array(x, 0)),
+            Literal::Generator(x) => Ok(Iteration::Generator(x)),
+            // Collections are looped over as they were when the loop started.
+            Literal::Set(x) => Ok(Iteration::snapshot(x.borrow().values())),
+            Literal::Deque(x) => Ok(Iteration::snapshot(x.borrow().values())),
+            Literal::PriorityQueue(x) => Ok(Iteration::snapshot(x.borrow().values())),
+            x => Err(RuntimeError::new(
+                keyword,
+                format!(
+                    "Can only loop over arrays, generators and collections, not {}.",
+                    Literal::type_name(&x)
+                ),
+            )
+            .with_code("E0202")
+            .into()),
+        }
+    }
+
+    fn snapshot(values: Vec<Literal>) -> Iteration {
+        Iteration::Array(Rc::new(RefCell::new(values)), 0)
+    }
+
+    pub fn next(
+        &mut self,
+        interpreter: &Interpreter,
+        keyword: &Token,
+    ) -> Result<Option<Literal>, Unwind> {
+        match self {
+            Iteration::Array(array, index) => {
+                let next = array.borrow().get(*index).cloned();
+                *index += 1;
+                Ok(next)
+            }
+            Iteration::Generator(generator) => Generator::resume(generator, interpreter, keyword),
+        }
+    }
+}
+
+impl Generator {
+    pub fn new(function: Rc<Function>, arguments: Vec<(String, Literal)>) -> Generator {
+        Generator {
+            function,
+            arguments,
+            frames: Vec::new(),
+            state: State::Suspended,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    // Runs the body until the next yield, giving back the yielded value, or None once it has finished.
+    pub fn resume(
+        generator: &Rc<RefCell<Generator>>,
+        interpreter: &Interpreter,
+        token: &Token,
+    ) -> Result<Option<Literal>, Unwind> {
+        let (function, mut frames, arguments) = {
+            let mut generator = generator.borrow_mut();
+            match generator.state {
+                State::Done => return Ok(None),
+                State::Running => {
+                    return Err(RuntimeError::new(token, "Generator is already running.").into())
+                }
+                State::Suspended => generator.state = State::Running,
+            }
+
+            (
+                generator.function.clone(),
+                mem::take(&mut generator.frames),
+                mem::take(&mut generator.arguments),
+            )
+        };
+
+        let closure = interpreter.with_environment(function.closure.clone());
+        let result = closure.resume_block(&function.declaration.body, &mut frames, 0, arguments);
+
+        let mut generator = generator.borrow_mut();
+        match result {
+            Ok(Flow::Suspend(x)) => {
+                generator.frames = frames;
+                generator.state = State::Suspended;
+                Ok(Some(x))
+            }
+            // An Err returned by `?` is handed out as the last value, so whatever is going
+            // through the generator sees it rather than it just stopping.
+            Err(Unwind::Return(Literal::Variant(x)))
+                if x.is_variant_of(interpreter.result_type(), "Err") =>
+            {
+                generator.state = State::Done;
+                Ok(Some(Literal::Variant(x)))
+            }
+            // Returning anything else from a generator just finishes it early.
+            Ok(Flow::Done) | Err(Unwind::Return(_)) => {
+                generator.state = State::Done;
+                Ok(None)
+            }
+            Err(x) => {
+                generator.state = State::Done;
+                Err(x)
+            }
+        }
+    }
+}
+
+// Generators are only ever equal to themselves.
+impl PartialEq for Generator {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<generator {}>", self.function.declaration.name.lexeme)
+    }
+}
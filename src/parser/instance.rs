@@ -0,0 +1,53 @@
+use super::expr::Literal;
+use indexmap::IndexMap;
+use std::fmt;
+use std::rc::Rc;
+
+// The runtime side of a `struct` declaration. Calling it builds an Instance.
+#[derive(Debug, PartialEq)]
+pub struct StructType {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Instance {
+    pub structure: Rc<StructType>,
+    // In declaration order, or for an Object, the order its keys came in.
+    pub fields: IndexMap<String, Literal>,
+}
+
+impl Instance {
+    pub fn get(&self, name: &str) -> Option<Literal> {
+        self.fields.get(name).cloned()
+    }
+
+    // Returns false if the struct doesn't declare the field.
+    pub fn set(&mut self, name: &str, value: Literal) -> bool {
+        match self.fields.get_mut(name) {
+            Some(x) => {
+                *x = value;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for StructType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<struct {}>", self.name)
+    }
+}
+
+impl fmt::Display for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect();
+
+        write!(f, "{} {{ {} }}", self.structure.name, fields.join(", "))
+    }
+}
@@ -1,23 +1,93 @@
+use super::clock::Clock;
+use super::collections::{self, Deque, PriorityQueue, Set};
+use super::enumeration::{EnumType, Variant};
+use super::environment::Environment;
 use super::expr::{Expr, ExprVisitor};
-use super::runtime_error::RuntimeError;
-use crate::error::Error;
-use crate::error::LogLevel;
-use crate::parser::expr::{Binary, Comma, Grouping, Literal, Ternary, Unary};
-use crate::scanner::token::Token;
+use super::function::Function;
+use super::generator::{Generator, Iteration};
+use super::instance::{Instance, StructType};
+use super::module::Modules;
+use super::native::NativeFunction;
+use super::native_object;
+use super::pattern::Pattern;
+use super::runtime_error::{RuntimeError, Unwind};
+use super::stdlib::{self, regex::Regex};
+use super::stmt::{self, FunctionKind, Stmt, StmtVisitor};
+use super::task::{self, EventLoop, Task};
+use crate::error::Sources;
+use crate::parser::expr::{
+    Array, Assign, Await, Binary, Call, Comma, Get, Grouping, Index, Literal, Match, Propagate,
+    Ternary, Unary, Variable,
+};
+use crate::scanner::token::{SourceId, Token};
 use crate::scanner::token_type::TokenType;
+use indexmap::IndexMap;
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
 
+// Cloning an Interpreter is cheap, the environment is shared.
 #[derive(Clone)]
-pub struct Interpreter;
+pub struct Interpreter {
+    environment: Rc<RefCell<Environment>>,
+    // Just the built ins, every module's top level scope is inside this.
+    globals: Rc<RefCell<Environment>>,
+    event_loop: Rc<RefCell<EventLoop>>,
+    modules: Rc<RefCell<Modules>>,
+    // Everything that's been run, for diagnostics to show.
+    sources: Rc<RefCell<Sources>>,
+    // The global Error, which caught runtime errors are instances of.
+    error_type: Rc<StructType>,
+    // The global Result, the only enum `?` unwraps.
+    result_type: Rc<EnumType>,
+}
 
 // This is how we get the enum into something Rust can do arithmetic on.
 // Trust me, it's better this way.
 // Long and verbose but not cursed.
 
-fn expected_type_msg(expected: &str, got: &str) -> String {
-    format!("Expected {} type, got {}.", expected, got)
+// The value was the wrong type for what it was used for.
+pub(super) fn type_error(token: &Token, expected: &str, got: &Literal) -> RuntimeError {
+    let message = format!(
+        "Expected {} type, got {}.",
+        expected,
+        Literal::type_name(got)
+    );
+    RuntimeError::new(token, message).with_code("E0202")
 }
 
-struct LiteralWithToken(Literal, Token);
+// Both sides of an arithmetic or comparison operator, which only work on Numbers.
+// Otherwise it's an error at the operator, with each side labelled with what it was.
+fn number_operands(binary: &Binary, left: &Literal, right: &Literal) -> Result<(f64, f64), Unwind> {
+    match (left, right) {
+        (Literal::Number(x), Literal::Number(y)) => Ok((*x, *y)),
+        _ => Err(RuntimeError::new(
+            &binary.operator,
+            format!("Operands of '{}' must be Numbers.", binary.operator.lexeme),
+        )
+        .with_code("E0202")
+        .with_label(
+            binary.left.span(),
+            format!("left operand is {}", Literal::type_name(left)),
+        )
+        .with_label(
+            binary.right.span(),
+            format!("right operand is {}", Literal::type_name(right)),
+        )
+        .into()),
+    }
+}
+
+// A value paired with the token to blame if it's the wrong type. Implementing TryFrom for this
+// is how a type becomes usable as an argument to a host function, see Vm::register_fn.
+pub struct LiteralWithToken(pub Literal, pub Token);
+
+impl TryFrom<LiteralWithToken> for Literal {
+    type Error = RuntimeError;
+
+    fn try_from(value: LiteralWithToken) -> Result<Self, Self::Error> {
+        Ok(value.0)
+    }
+}
 
 impl TryFrom<LiteralWithToken> for f64 {
     type Error = RuntimeError;
@@ -27,11 +97,7 @@ impl TryFrom<LiteralWithToken> for f64 {
 
         match value.0 {
             Number(x) => Ok(x),
-            x => Err(RuntimeError {
-                token: value.1,
-                log_level: LogLevel::Error,
-                message: expected_type_msg("Number", &Literal::type_name(&x)),
-            }),
+            x => Err(type_error(&value.1, "Number", &x)),
         }
     }
 }
@@ -44,11 +110,7 @@ impl TryFrom<LiteralWithToken> for String {
 
         match value.0 {
             String(x) => Ok(x),
-            x => Err(RuntimeError {
-                token: value.1,
-                log_level: LogLevel::Error,
-                message: expected_type_msg("String", &Literal::type_name(&x)),
-            }),
+            x => Err(type_error(&value.1, "String", &x)),
         }
     }
 }
@@ -62,92 +124,735 @@ impl TryFrom<LiteralWithToken> for bool {
         match value.0 {
             True => Ok(true),
             False => Ok(false),
-            x => Err(RuntimeError {
-                token: value.1,
-                log_level: LogLevel::Error,
-                message: expected_type_msg("Bool", &Literal::type_name(&x)),
-            }),
+            x => Err(type_error(&value.1, "Bool", &x)),
         }
     }
 }
 
+impl TryFrom<LiteralWithToken> for Rc<RefCell<Vec<Literal>>> {
+    type Error = RuntimeError;
+
+    fn try_from(value: LiteralWithToken) -> Result<Self, Self::Error> {
+        match value.0 {
+            Literal::Array(x) => Ok(x),
+            x => Err(type_error(&value.1, "Array", &x)),
+        }
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Interpreter {
-    pub fn interpret(&self, mut expr: Expr, source: &str) -> Result<(), ()> {
-        let evaluated = self.evaluate(&mut expr);
+    pub fn new() -> Interpreter {
+        Interpreter::with_clock(Clock::real())
+    }
+
+    // The clock is what clock(), sleep(), time.now() and the event loop go by, see Clock::new_virtual for tests.
+    pub fn with_clock(clock: Clock) -> Interpreter {
+        let mut globals = Environment::new(None);
+        let error_type = Rc::new(RuntimeError::error_type());
+        globals.define("Error".to_string(), Literal::Struct(error_type.clone()));
+
+        let result_type = Rc::new(EnumType::result());
+        for variant in ["Ok", "Err"] {
+            globals.define(variant.to_string(), result_type.get(variant).unwrap());
+        }
+        globals.define("Result".to_string(), Literal::Enum(result_type.clone()));
+
+        stdlib::define(&mut globals);
+        for native in [
+            NativeFunction::new("clock", 0, stdlib::time::clock),
+            NativeFunction::new("sleep", 1, task::sleep),
+            NativeFunction::new("spawn", 1, task::spawn),
+        ]
+        .into_iter()
+        .chain(collections::constructors())
+        {
+            globals.define(native.name.clone(), Literal::Native(Rc::new(native)));
+        }
+
+        let globals = Rc::new(RefCell::new(globals));
+        Interpreter {
+            environment: Rc::new(RefCell::new(Environment::new(Some(globals.clone())))),
+            globals,
+            event_loop: Rc::new(RefCell::new(EventLoop::new(clock))),
+            modules: Rc::new(RefCell::new(Modules::new())),
+            sources: Rc::new(RefCell::new(Sources::new())),
+            error_type,
+            result_type,
+        }
+    }
+
+    // Runs a statement of a script.
+    pub fn interpret(&self, stmt: Stmt) -> Result<(), Box<RuntimeError>> {
+        self.execute(&stmt).map_err(Unwind::into_boxed_error)
+    }
+
+    // Runs a line typed into the REPL, where an expression's value is printed.
+    pub fn interpret_line(&self, stmt: Stmt) -> Result<(), Box<RuntimeError>> {
+        let executed = match &stmt {
+            Stmt::Expression(x) => self.evaluate(&x.expression).map(|x| println!("{}", x)),
+            x => self.execute(x),
+        };
+        executed.map_err(Unwind::into_boxed_error)
+    }
+
+    // Runs a statement for a host, which gets an expression statement's value instead of it
+    // being printed.
+    pub fn run_statement(&self, stmt: &Stmt) -> Result<Literal, Box<RuntimeError>> {
+        let executed = match stmt {
+            Stmt::Expression(x) => self.evaluate(&x.expression),
+            x => self.execute(x).map(|_| Literal::Null),
+        };
+        executed.map_err(Unwind::into_boxed_error)
+    }
+
+    // Variables in the script's top level scope, falling back to the builtins.
+    pub fn get_variable(&self, name: &str) -> Option<Literal> {
+        self.environment.borrow().lookup(name)
+    }
+
+    pub fn set_variable(&self, name: &str, value: Literal) {
+        self.environment
+            .borrow_mut()
+            .define(name.to_string(), value);
+    }
+
+    // Keeps the source for diagnostics to show, it has to be parsed with the id this gives back.
+    pub fn add_source(&self, name: &str, text: &str) -> SourceId {
+        self.sources.borrow_mut().add(name, text)
+    }
+
+    pub fn sources(&self) -> Ref<'_, Sources> {
+        self.sources.borrow()
+    }
+
+    // Makes the function a builtin, visible from every module.
+    pub fn define_native(&self, native: NativeFunction) {
+        let name = native.name.clone();
+        self.globals
+            .borrow_mut()
+            .define(name, Literal::Native(Rc::new(native)));
+    }
+
+    // An interpreter that runs in the given scope, but shares the event loop with this one.
+    pub(super) fn with_environment(&self, environment: Rc<RefCell<Environment>>) -> Interpreter {
+        Interpreter {
+            environment,
+            globals: self.globals.clone(),
+            event_loop: self.event_loop.clone(),
+            modules: self.modules.clone(),
+            sources: self.sources.clone(),
+            error_type: self.error_type.clone(),
+            result_type: self.result_type.clone(),
+        }
+    }
+
+    pub(super) fn globals(&self) -> &Rc<RefCell<Environment>> {
+        &self.globals
+    }
+
+    pub(super) fn event_loop(&self) -> &Rc<RefCell<EventLoop>> {
+        &self.event_loop
+    }
+
+    pub(super) fn modules(&self) -> &Rc<RefCell<Modules>> {
+        &self.modules
+    }
+
+    pub(super) fn error_type(&self) -> &Rc<StructType> {
+        &self.error_type
+    }
+
+    pub(super) fn result_type(&self) -> &Rc<EnumType> {
+        &self.result_type
+    }
+
+    pub(super) fn evaluate(&self, expr: &Expr) -> Result<Literal, Unwind> {
+        expr.accept::<Result<Literal, Unwind>>(Box::new(self.clone()))
+    }
+
+    pub(super) fn execute(&self, stmt: &Stmt) -> Result<(), Unwind> {
+        stmt.accept::<Result<(), Unwind>>(Box::new(self.clone()))
+    }
+
+    fn check_arity(expected: usize, got: usize, paren: &Token) -> Result<(), Unwind> {
+        match expected == got {
+            true => Ok(()),
+            false => Err(RuntimeError::new(
+                paren,
+                format!("Expected {} arguments but got {}.", expected, got),
+            )
+            .with_code("E0206")
+            .into()),
+        }
+    }
+
+    fn check_native_arity(
+        native: &NativeFunction,
+        got: usize,
+        paren: &Token,
+    ) -> Result<(), Unwind> {
+        if native.optional == 0 {
+            return Interpreter::check_arity(native.arity, got, paren);
+        }
+
+        let most = native.arity + native.optional;
+        match (native.arity..=most).contains(&got) {
+            true => Ok(()),
+            false => Err(RuntimeError::new(
+                paren,
+                format!(
+                    "Expected {} to {} arguments but got {}.",
+                    native.arity, most, got
+                ),
+            )
+            .with_code("E0206")
+            .into()),
+        }
+    }
+
+    // Instances can be indexed by field name too, for fields that aren't identifiers, like
+    // the keys of parsed JSON.
+    fn field_name(index: Literal, bracket: &Token) -> Result<String, Unwind> {
+        Ok(String::try_from(LiteralWithToken(index, bracket.clone()))?)
+    }
 
-        match evaluated {
-            Ok(x) => {
-                println!("{}", x);
-                Ok(())
+    // Checks that `index` is a whole number that fits inside an array of `len` elements.
+    fn array_index(len: usize, index: Literal, bracket: &Token) -> Result<usize, Unwind> {
+        let index = f64::try_from(LiteralWithToken(index, bracket.clone()))?;
+
+        if index.fract() != 0.0 || index < 0.0 || index >= len as f64 {
+            return Err(RuntimeError::new(
+                bracket,
+                format!("Index {} out of range for array of length {}.", index, len),
+            )
+            .with_code("E0207")
+            .into());
+        }
+
+        Ok(index as usize)
+    }
+
+    fn assign(&self, target: &Expr, value: Literal) -> Result<(), Unwind> {
+        match target {
+            Expr::Variable(x) => Ok(self.environment.borrow_mut().assign(&x.name, value)?),
+            Expr::Get(x) => match self.evaluate(x.object.as_ref())? {
+                Literal::Object(object) => native_object::set(&object, &x.name, value),
+                Literal::Instance(instance) => {
+                    if instance.borrow_mut().set(&x.name.lexeme, value) {
+                        return Ok(());
+                    }
+                    Err(RuntimeError::new(
+                        &x.name,
+                        format!(
+                            "Struct '{}' has no field '{}'.",
+                            instance.borrow().structure.name,
+                            x.name.lexeme
+                        ),
+                    )
+                    .with_code("E0203")
+                    .into())
+                }
+                object => Err(RuntimeError::new(
+                    &x.name,
+                    format!(
+                        "Only instances have fields, got {}.",
+                        Literal::type_name(&object)
+                    ),
+                )
+                .with_code("E0202")
+                .into()),
+            },
+            Expr::Index(x) => {
+                let object = self.evaluate(x.object.as_ref())?;
+                let index = self.evaluate(x.index.as_ref())?;
+
+                match object {
+                    Literal::Array(array) => {
+                        let len = array.borrow().len();
+                        array.borrow_mut()[Interpreter::array_index(len, index, &x.bracket)?] =
+                            value;
+                        Ok(())
+                    }
+                    Literal::Instance(instance) => {
+                        let name = Interpreter::field_name(index, &x.bracket)?;
+                        if instance.borrow_mut().set(&name, value) {
+                            return Ok(());
+                        }
+                        Err(RuntimeError::new(
+                            &x.bracket,
+                            format!(
+                                "Struct '{}' has no field '{}'.",
+                                instance.borrow().structure.name,
+                                name
+                            ),
+                        )
+                        .with_code("E0203")
+                        .into())
+                    }
+                    object => Err(type_error(&x.bracket, "Array or Instance", &object).into()),
+                }
             }
-            Err(e) => {
-                e.report(source);
-                Err(())
+            _ => unreachable!("the parser only allows variables, properties and indexes"),
+        }
+    }
+
+    // An interpreter that shares everything with this one, but has its own scope.
+    pub(super) fn with_bindings(&self, bindings: Vec<(String, Literal)>) -> Interpreter {
+        let mut environment = Environment::new(Some(self.environment.clone()));
+        for (name, value) in bindings {
+            environment.define(name, value);
+        }
+
+        self.with_environment(Rc::new(RefCell::new(environment)))
+    }
+
+    // Runs the statements in their own scope, which goes away afterwards.
+    fn execute_block(
+        &self,
+        statements: &[Stmt],
+        bindings: Vec<(String, Literal)>,
+    ) -> Result<(), Unwind> {
+        let scope = self.with_bindings(bindings);
+
+        for statement in statements.iter() {
+            scope.execute(statement)?;
+        }
+
+        Ok(())
+    }
+
+    // Binds the let's patterns to their values, which are Null for any missing.
+    pub(super) fn bind_let(
+        &self,
+        let_stmt: &stmt::Let,
+        mut values: Vec<Literal>,
+    ) -> Result<(), Unwind> {
+        values.resize(let_stmt.patterns.len(), Literal::Null);
+
+        let mut bindings = Vec::new();
+        for (pattern, value) in let_stmt.patterns.iter().zip(values.iter()) {
+            if !Interpreter::match_pattern(pattern, value, &mut bindings) {
+                return Err(RuntimeError::new(
+                    &let_stmt.keyword,
+                    format!("{} doesn't match the pattern {}.", value, pattern),
+                )
+                .with_code("E0208")
+                .into());
+            }
+        }
+
+        let mut environment = self.environment.borrow_mut();
+        for (name, value) in bindings {
+            environment.define(name, value);
+        }
+        Ok(())
+    }
+
+    // Binds one value from a for loop's iterable to its pattern.
+    pub(super) fn bind_for(
+        &self,
+        for_stmt: &stmt::For,
+        value: &Literal,
+        bindings: &mut Vec<(String, Literal)>,
+    ) -> Result<(), Unwind> {
+        match Interpreter::match_pattern(&for_stmt.pattern, value, bindings) {
+            true => Ok(()),
+            false => Err(RuntimeError::new(
+                &for_stmt.keyword,
+                format!("{} doesn't match the pattern {}.", value, for_stmt.pattern),
+            )
+            .with_code("E0208")
+            .into()),
+        }
+    }
+
+    // Returns whether the value matched, pushing any names the pattern binds into `bindings`.
+    fn match_pattern(
+        pattern: &Pattern,
+        value: &Literal,
+        bindings: &mut Vec<(String, Literal)>,
+    ) -> bool {
+        match pattern {
+            Pattern::Wildcard => true,
+            Pattern::Binding(name) => {
+                bindings.push((name.lexeme.clone(), value.clone()));
+                true
+            }
+            Pattern::Literal(x) => x == value,
+            Pattern::Range(range) => match (&range.start, &range.end, value) {
+                (Literal::Number(start), Literal::Number(end), Literal::Number(x)) => {
+                    start <= x && if range.inclusive { x <= end } else { x < end }
+                }
+                (Literal::String(start), Literal::String(end), Literal::String(x)) => {
+                    start <= x && if range.inclusive { x <= end } else { x < end }
+                }
+                _ => false,
+            },
+            Pattern::Struct(structure) => {
+                if let Some(name) = &structure.name {
+                    match value {
+                        Literal::Instance(x) if x.borrow().structure.name == name.lexeme => (),
+                        _ => return false,
+                    }
+                }
+
+                structure.fields.iter().all(|(name, pattern)| {
+                    let field = match value {
+                        Literal::Instance(x) => x.borrow().get(&name.lexeme),
+                        Literal::Variant(x) => x.get(&name.lexeme),
+                        _ => None,
+                    };
+
+                    match field {
+                        Some(field) => Interpreter::match_pattern(pattern, &field, bindings),
+                        None => false,
+                    }
+                })
+            }
+            Pattern::Array(pattern) => {
+                let array = match value {
+                    Literal::Array(x) => x.borrow(),
+                    _ => return false,
+                };
+
+                let long_enough = match pattern.rest {
+                    Some(_) => array.len() >= pattern.elements.len(),
+                    None => array.len() == pattern.elements.len(),
+                };
+                if !long_enough {
+                    return false;
+                }
+
+                let elements_match = pattern
+                    .elements
+                    .iter()
+                    .zip(array.iter())
+                    .all(|(pattern, value)| Interpreter::match_pattern(pattern, value, bindings));
+
+                match &pattern.rest {
+                    Some(rest) if elements_match => {
+                        let rest_array = array[pattern.elements.len()..].to_vec();
+                        Interpreter::match_pattern(
+                            rest,
+                            &Literal::Array(Rc::new(RefCell::new(rest_array))),
+                            bindings,
+                        )
+                    }
+                    _ => elements_match,
+                }
+            }
+            Pattern::Variant(pattern) => {
+                let variant = match value {
+                    Literal::Variant(x) => x,
+                    _ => return false,
+                };
+
+                if variant.variant_type.name != pattern.name.lexeme {
+                    return false;
+                }
+                if let Some(enum_name) = &pattern.enum_name {
+                    if variant.variant_type.enum_name != enum_name.lexeme {
+                        return false;
+                    }
+                }
+
+                match &pattern.fields {
+                    Some(fields) => {
+                        fields.len() == variant.values.len()
+                            && fields
+                                .iter()
+                                .zip(variant.values.iter())
+                                .all(|(pattern, value)| {
+                                    Interpreter::match_pattern(pattern, value, bindings)
+                                })
+                    }
+                    None => true,
+                }
+            }
+        }
+    }
+
+    // `value is Type`, where Type is a struct, an enum or one of its variants.
+    fn is_type(value: &Literal, type_value: &Literal, operator: &Token) -> Result<bool, Unwind> {
+        match type_value {
+            Literal::Struct(structure) => Ok(match value {
+                // Two structs with the same name and fields are still different types.
+                Literal::Instance(x) => Rc::ptr_eq(&x.borrow().structure, structure),
+                _ => false,
+            }),
+            Literal::Enum(enumeration) => Ok(match value {
+                Literal::Variant(x) => x.variant_type.enum_name == enumeration.name,
+                _ => false,
+            }),
+            Literal::VariantType(variant_type) => Ok(match value {
+                Literal::Variant(x) => x.variant_type == *variant_type,
+                _ => false,
+            }),
+            // Variants without a payload are values rather than types, but checking against them should still work.
+            Literal::Variant(unit) if unit.values.is_empty() => Ok(match value {
+                Literal::Variant(x) => x.variant_type == unit.variant_type,
+                _ => false,
+            }),
+            x => Err(type_error(operator, "Struct, Enum or Variant", x).into()),
+        }
+    }
+}
+
+impl StmtVisitor<Result<(), Unwind>> for Interpreter {
+    fn visit_expression(&self, expression: &stmt::Expression) -> Result<(), Unwind> {
+        self.evaluate(&expression.expression)?;
+        Ok(())
+    }
+
+    fn visit_let(&self, let_stmt: &stmt::Let) -> Result<(), Unwind> {
+        let mut values = Vec::new();
+        for initialiser in let_stmt.initialisers.iter() {
+            values.push(self.evaluate(initialiser)?);
+        }
+        self.bind_let(let_stmt, values)
+    }
+
+    fn visit_struct(&self, struct_stmt: &stmt::Struct) -> Result<(), Unwind> {
+        let structure = StructType {
+            name: struct_stmt.name.lexeme.clone(),
+            fields: struct_stmt
+                .fields
+                .iter()
+                .map(|x| x.lexeme.clone())
+                .collect(),
+        };
+
+        self.environment.borrow_mut().define(
+            struct_stmt.name.lexeme.clone(),
+            Literal::Struct(Rc::new(structure)),
+        );
+        Ok(())
+    }
+
+    fn visit_enum(&self, enum_stmt: &stmt::Enum) -> Result<(), Unwind> {
+        let enumeration = EnumType::new(
+            &enum_stmt.name.lexeme,
+            enum_stmt
+                .variants
+                .iter()
+                .map(|x| {
+                    (
+                        x.name.lexeme.clone(),
+                        x.fields.iter().map(|x| x.lexeme.clone()).collect(),
+                    )
+                })
+                .collect(),
+        );
+
+        self.environment.borrow_mut().define(
+            enum_stmt.name.lexeme.clone(),
+            Literal::Enum(Rc::new(enumeration)),
+        );
+        Ok(())
+    }
+
+    fn visit_block(&self, block: &stmt::Block) -> Result<(), Unwind> {
+        self.execute_block(&block.statements, Vec::new())
+    }
+
+    fn visit_print(&self, print: &stmt::Print) -> Result<(), Unwind> {
+        println!("{}", self.evaluate(&print.expression)?);
+        Ok(())
+    }
+
+    fn visit_throw(&self, throw: &stmt::Throw) -> Result<(), Unwind> {
+        let value = self.evaluate(&throw.value)?;
+        Err(Unwind::Throw(value, Box::new(throw.keyword.clone())))
+    }
+
+    fn visit_try(&self, try_stmt: &stmt::Try) -> Result<(), Unwind> {
+        let result = match (
+            self.execute_block(&try_stmt.try_block, Vec::new()),
+            &try_stmt.catch_block,
+        ) {
+            (Err(unwind), Some(catch_block)) => match unwind.caught_value(&self.error_type) {
+                Ok(caught) => {
+                    let bindings = match &try_stmt.catch_name {
+                        Some(name) => vec![(name.lexeme.clone(), caught)],
+                        None => Vec::new(),
+                    };
+                    self.execute_block(catch_block, bindings)
+                }
+                Err(unwind) => Err(unwind),
+            },
+            (result, _) => result,
+        };
+
+        // Anything thrown out of the finally block replaces whatever was already on its way out.
+        if let Some(finally_block) = &try_stmt.finally_block {
+            self.execute_block(finally_block, Vec::new())?;
+        }
+
+        result
+    }
+
+    fn visit_if(&self, if_stmt: &stmt::If) -> Result<(), Unwind> {
+        match Literal::is_truthy(&self.evaluate(&if_stmt.condition)?) {
+            true => self.execute_block(&if_stmt.then_branch, Vec::new()),
+            false => match &if_stmt.else_branch {
+                Some(x) => self.execute_block(x, Vec::new()),
+                None => Ok(()),
+            },
+        }
+    }
+
+    fn visit_while(&self, while_stmt: &stmt::While) -> Result<(), Unwind> {
+        while Literal::is_truthy(&self.evaluate(&while_stmt.condition)?) {
+            self.execute_block(&while_stmt.body, Vec::new())?;
+        }
+        Ok(())
+    }
+
+    fn visit_for(&self, for_stmt: &stmt::For) -> Result<(), Unwind> {
+        let iterable = self.evaluate(&for_stmt.iterable)?;
+        let mut iteration = Iteration::new(iterable, &for_stmt.keyword)?;
+
+        while let Some(value) = iteration.next(self, &for_stmt.keyword)? {
+            let mut bindings = Vec::new();
+            self.bind_for(for_stmt, &value, &mut bindings)?;
+            self.execute_block(&for_stmt.body, bindings)?;
+        }
+        Ok(())
+    }
+
+    fn visit_function(&self, function: &Rc<stmt::Function>) -> Result<(), Unwind> {
+        let value = Function {
+            declaration: function.clone(),
+            closure: self.environment.clone(),
+        };
+
+        self.environment.borrow_mut().define(
+            function.name.lexeme.clone(),
+            Literal::Function(Rc::new(value)),
+        );
+        Ok(())
+    }
+
+    fn visit_return(&self, return_stmt: &stmt::Return) -> Result<(), Unwind> {
+        let value = match &return_stmt.value {
+            Some(x) => self.evaluate(x)?,
+            None => Literal::Null,
+        };
+
+        Err(Unwind::Return(value))
+    }
+
+    fn visit_import(&self, import: &stmt::Import) -> Result<(), Unwind> {
+        let module = self.import(import)?;
+
+        let mut environment = self.environment.borrow_mut();
+        match &import.names {
+            Some(names) => {
+                for name in names {
+                    let value = module.get(name).ok_or_else(|| {
+                        RuntimeError::new(
+                            name,
+                            format!("Module '{}' doesn't export '{}'.", module.name, name.lexeme),
+                        )
+                        .with_code("E0203")
+                    })?;
+                    environment.define(name.lexeme.clone(), value);
+                }
             }
+            None => environment.define(module.name.clone(), Literal::Module(module)),
+        }
+        Ok(())
+    }
+
+    fn visit_export(&self, export: &stmt::Export) -> Result<(), Unwind> {
+        self.execute(&export.declaration)?;
+
+        let mut names = Vec::new();
+        match export.declaration.as_ref() {
+            Stmt::Let(x) => x.patterns.iter().for_each(|x| x.bindings(&mut names)),
+            Stmt::Function(x) => names.push(&x.name),
+            Stmt::Struct(x) => names.push(&x.name),
+            Stmt::Enum(x) => names.push(&x.name),
+            _ => unreachable!("the parser only allows exporting declarations"),
         }
+
+        self.export(names.iter().map(|x| x.lexeme.clone()).collect());
+        Ok(())
     }
-    fn evaluate(&self, expr: &mut Expr) -> Result<Literal, RuntimeError> {
-        expr.accept::<Result<Literal, RuntimeError>>(Box::new(self.clone()))
+
+    // Generator bodies are run by Generator::resume, which handles yields itself.
+    fn visit_yield(&self, yield_stmt: &stmt::Yield) -> Result<(), Unwind> {
+        Err(RuntimeError::new(&yield_stmt.keyword, "Can't yield outside of a generator.").into())
     }
 }
 
-impl ExprVisitor<Result<Literal, RuntimeError>> for Interpreter {
-    fn visit_grouping(&self, grouping: &mut Grouping) -> Result<Literal, RuntimeError> {
-        self.evaluate(grouping.expression.as_mut())
-    }
-
-    fn visit_binary(&self, binary: &mut Binary) -> Result<Literal, RuntimeError> {
-        let left = self.evaluate(binary.left.as_mut())?;
-        let right = self.evaluate(binary.right.as_mut())?;
-
-        return match binary.operator.token {
-            TokenType::Minus => Ok(Literal::Number(
-                f64::try_from(LiteralWithToken(left, binary.operator.clone()))?
-                    - f64::try_from(LiteralWithToken(right, binary.operator.clone()))?,
-            )),
-            TokenType::Plus => Ok(Literal::Number(
-                f64::try_from(LiteralWithToken(left, binary.operator.clone()))?
-                    + f64::try_from(LiteralWithToken(right, binary.operator.clone()))?,
-            )),
-            TokenType::Slash => Ok(Literal::Number(
-                f64::try_from(LiteralWithToken(left, binary.operator.clone()))?
-                    / f64::try_from(LiteralWithToken(right, binary.operator.clone()))?,
-            )),
-            TokenType::Star => Ok(Literal::Number(
-                f64::try_from(LiteralWithToken(left, binary.operator.clone()))?
-                    * f64::try_from(LiteralWithToken(right, binary.operator.clone()))?,
-            )),
+impl ExprVisitor<Result<Literal, Unwind>> for Interpreter {
+    fn visit_grouping(&self, grouping: &Grouping) -> Result<Literal, Unwind> {
+        self.evaluate(grouping.expression.as_ref())
+    }
+
+    fn visit_binary(&self, binary: &Binary) -> Result<Literal, Unwind> {
+        let left = self.evaluate(binary.left.as_ref())?;
+        let right = self.evaluate(binary.right.as_ref())?;
+
+        match binary.operator.token {
+            TokenType::Minus => {
+                let (left, right) = number_operands(binary, &left, &right)?;
+                Ok(Literal::Number(left - right))
+            }
+            TokenType::Plus => {
+                let (left, right) = number_operands(binary, &left, &right)?;
+                Ok(Literal::Number(left + right))
+            }
+            TokenType::Slash => {
+                let (left, right) = number_operands(binary, &left, &right)?;
+
+                if right == 0.0 {
+                    return Err(RuntimeError::new(&binary.operator, "Division by zero.")
+                        .with_code("E0205")
+                        .with_label(binary.right.span(), "this is 0")
+                        .into());
+                }
+
+                Ok(Literal::Number(left / right))
+            }
+            TokenType::Star => {
+                let (left, right) = number_operands(binary, &left, &right)?;
+                Ok(Literal::Number(left * right))
+            }
             TokenType::Greater => {
-                if f64::try_from(LiteralWithToken(left, binary.operator.clone()))?
-                    > f64::try_from(LiteralWithToken(right, binary.operator.clone()))?
-                {
+                let (left, right) = number_operands(binary, &left, &right)?;
+                if left > right {
                     Ok(Literal::True)
                 } else {
                     Ok(Literal::False)
                 }
             }
             TokenType::GreaterEqual => {
-                if f64::try_from(LiteralWithToken(left, binary.operator.clone()))?
-                    >= f64::try_from(LiteralWithToken(right, binary.operator.clone()))?
-                {
+                let (left, right) = number_operands(binary, &left, &right)?;
+                if left >= right {
                     Ok(Literal::True)
                 } else {
                     Ok(Literal::False)
                 }
             }
             TokenType::Less => {
-                if f64::try_from(LiteralWithToken(left, binary.operator.clone()))?
-                    < f64::try_from(LiteralWithToken(right, binary.operator.clone()))?
-                {
+                let (left, right) = number_operands(binary, &left, &right)?;
+                if left < right {
                     Ok(Literal::True)
                 } else {
                     Ok(Literal::False)
                 }
             }
             TokenType::LessEqual => {
-                if f64::try_from(LiteralWithToken(left, binary.operator.clone()))?
-                    <= f64::try_from(LiteralWithToken(right, binary.operator.clone()))?
-                {
+                let (left, right) = number_operands(binary, &left, &right)?;
+                if left <= right {
                     Ok(Literal::True)
                 } else {
                     Ok(Literal::False)
@@ -167,47 +872,316 @@ impl ExprVisitor<Result<Literal, RuntimeError>> for Interpreter {
                     Ok(Literal::False)
                 }
             }
-
-            _ => {
-                return Err(RuntimeError {
-                    token: binary.operator.clone(),
-                    log_level: LogLevel::Error,
-                    message: "Expected two numbers on each side of the operator.".to_string(),
-                })
+            TokenType::Is => {
+                if Interpreter::is_type(&left, &right, &binary.operator)? {
+                    Ok(Literal::True)
+                } else {
+                    Ok(Literal::False)
+                }
             }
-        };
+
+            _ => Err(RuntimeError::new(
+                &binary.operator,
+                "Expected two numbers on each side of the operator.",
+            )
+            .with_code("E0202")
+            .into()),
+        }
     }
 
-    fn visit_literal(&self, literal: &mut Literal) -> Result<Literal, RuntimeError> {
+    fn visit_literal(&self, literal: &Literal) -> Result<Literal, Unwind> {
         Ok(literal.clone())
     }
 
-    fn visit_unary(&self, unary: &mut Unary) -> Result<Literal, RuntimeError> {
-        let right = self.evaluate(unary.expression.as_mut())?;
+    fn visit_unary(&self, unary: &Unary) -> Result<Literal, Unwind> {
+        let right = self.evaluate(unary.expression.as_ref())?;
 
         match unary.operator.token {
-            TokenType::Minus => Ok(Literal::Number(f64::try_from(LiteralWithToken(
-                right,
-                unary.operator.clone(),
-            ))?)),
+            TokenType::Minus => match right {
+                Literal::Number(x) => Ok(Literal::Number(-x)),
+                x => Err(
+                    RuntimeError::new(&unary.operator, "Operand of '-' must be a Number.")
+                        .with_code("E0202")
+                        .with_label(
+                            unary.expression.span(),
+                            format!("operand is {}", Literal::type_name(&x)),
+                        )
+                        .into(),
+                ),
+            },
             TokenType::Bang => Ok(Literal::negate(&right)),
             _ => Ok(Literal::Null), // unreachable
         }
     }
 
-    fn visit_ternary(&self, ternary: &mut Ternary) -> Result<Literal, RuntimeError> {
-        let condition = self.evaluate(ternary.condition.as_mut())?;
-        let if_true = self.evaluate(ternary.if_true.as_mut())?;
-        let if_false = self.evaluate(ternary.if_false.as_mut())?;
+    fn visit_ternary(&self, ternary: &Ternary) -> Result<Literal, Unwind> {
+        let condition = self.evaluate(ternary.condition.as_ref())?;
 
+        // Only the branch that's taken gets evaluated, otherwise recursion would never stop.
         match Literal::is_truthy(&condition) {
-            true => Ok(if_true),
-            false => Ok(if_false),
+            true => self.evaluate(ternary.if_true.as_ref()),
+            false => self.evaluate(ternary.if_false.as_ref()),
         }
     }
 
-    fn visit_comma(&self, comma: &mut Comma) -> Result<Literal, RuntimeError> {
-        self.evaluate(comma.expr.as_mut())?;
-        Ok(self.evaluate(comma.next.as_mut())?)
+    fn visit_comma(&self, comma: &Comma) -> Result<Literal, Unwind> {
+        self.evaluate(comma.expr.as_ref())?;
+        self.evaluate(comma.next.as_ref())
+    }
+
+    fn visit_variable(&self, variable: &Variable) -> Result<Literal, Unwind> {
+        self.environment.borrow().get(&variable.name)
+    }
+
+    fn visit_call(&self, call: &Call) -> Result<Literal, Unwind> {
+        let callee = self.evaluate(call.callee.as_ref())?;
+
+        let mut arguments = Vec::new();
+        for argument in call.arguments.iter() {
+            arguments.push(self.evaluate(argument)?);
+        }
+
+        match callee {
+            Literal::Struct(structure) => {
+                Interpreter::check_arity(structure.fields.len(), arguments.len(), &call.paren)?;
+
+                let fields: IndexMap<String, Literal> =
+                    structure.fields.iter().cloned().zip(arguments).collect();
+
+                Ok(Literal::Instance(Rc::new(RefCell::new(Instance {
+                    structure,
+                    fields,
+                }))))
+            }
+            Literal::Function(function) => {
+                let declaration = &function.declaration;
+                Interpreter::check_arity(declaration.params.len(), arguments.len(), &call.paren)?;
+
+                let bindings = declaration
+                    .params
+                    .iter()
+                    .map(|x| x.lexeme.clone())
+                    .zip(arguments)
+                    .collect();
+
+                // Generators and tasks run none of the body yet, that waits for next() or await.
+                match declaration.kind {
+                    FunctionKind::Generator => {
+                        return Ok(Literal::Generator(Rc::new(RefCell::new(Generator::new(
+                            function.clone(),
+                            bindings,
+                        )))))
+                    }
+                    FunctionKind::Async => {
+                        return Ok(Literal::Task(Rc::new(RefCell::new(Task::new(
+                            function.clone(),
+                            bindings,
+                        )))))
+                    }
+                    FunctionKind::Function => (),
+                }
+
+                let closure = self.with_environment(function.closure.clone());
+
+                match closure.execute_block(&declaration.body, bindings) {
+                    Ok(()) => Ok(Literal::Null),
+                    Err(Unwind::Return(x)) => Ok(x),
+                    Err(x) => Err(x),
+                }
+            }
+            Literal::Native(native) => {
+                Interpreter::check_native_arity(&native, arguments.len(), &call.paren)?;
+                arguments.resize(native.arity + native.optional, Literal::Null);
+                (native.function)(self, arguments, &call.paren)
+            }
+            Literal::VariantType(variant_type) => {
+                Interpreter::check_arity(variant_type.fields.len(), arguments.len(), &call.paren)?;
+
+                Ok(Literal::Variant(Rc::new(Variant {
+                    variant_type,
+                    values: arguments,
+                })))
+            }
+            x => Err(RuntimeError::new(
+                &call.paren,
+                format!(
+                    "Can only call functions, structs and enum variants, not {}.",
+                    Literal::type_name(&x)
+                ),
+            )
+            .with_code("E0202")
+            .into()),
+        }
+    }
+
+    fn visit_get(&self, get: &Get) -> Result<Literal, Unwind> {
+        let object = self.evaluate(get.object.as_ref())?;
+
+        let undefined = || {
+            Unwind::from(
+                RuntimeError::new(
+                    &get.name,
+                    format!("Undefined property '{}'.", get.name.lexeme),
+                )
+                .with_code("E0203"),
+            )
+        };
+
+        match object {
+            Literal::Instance(x) => x.borrow().get(&get.name.lexeme).ok_or_else(undefined),
+            Literal::Variant(x) => x.get(&get.name.lexeme).ok_or_else(undefined),
+            Literal::Generator(x) => match get.name.lexeme.as_str() {
+                // Gives the next value, or Null once the generator has finished.
+                "next" => Ok(Literal::Native(Rc::new(NativeFunction::new(
+                    "next",
+                    0,
+                    move |interpreter, _, paren| {
+                        Ok(Generator::resume(&x, interpreter, paren)?.unwrap_or(Literal::Null))
+                    },
+                )))),
+                "done" => match x.borrow().is_done() {
+                    true => Ok(Literal::True),
+                    false => Ok(Literal::False),
+                },
+                _ => Err(undefined()),
+            },
+            Literal::Module(x) => x.get(&get.name).ok_or_else(|| {
+                RuntimeError::new(
+                    &get.name,
+                    format!("Module '{}' doesn't export '{}'.", x.name, get.name.lexeme),
+                )
+                .with_code("E0203")
+                .into()
+            }),
+            Literal::Task(x) => match get.name.lexeme.as_str() {
+                "done" => match x.borrow().is_done() {
+                    true => Ok(Literal::True),
+                    false => Ok(Literal::False),
+                },
+                _ => Err(undefined()),
+            },
+            Literal::Regex(x) => Regex::get(&x, &get.name).ok_or_else(undefined),
+            Literal::Set(x) => Set::get(&x, &get.name).ok_or_else(undefined),
+            Literal::Deque(x) => Deque::get(&x, &get.name).ok_or_else(undefined),
+            Literal::PriorityQueue(x) => PriorityQueue::get(&x, &get.name).ok_or_else(undefined),
+            Literal::Object(x) => native_object::get(&x, &get.name).ok_or_else(undefined),
+            Literal::Enum(x) => x.get(&get.name.lexeme).ok_or_else(|| {
+                RuntimeError::new(
+                    &get.name,
+                    format!("Enum '{}' has no variant '{}'.", x.name, get.name.lexeme),
+                )
+                .with_code("E0203")
+                .into()
+            }),
+            x => Err(RuntimeError::new(
+                &get.name,
+                format!(
+                    "Only instances have properties, got {}.",
+                    Literal::type_name(&x)
+                ),
+            )
+            .with_code("E0202")
+            .into()),
+        }
+    }
+
+    fn visit_assign(&self, assign: &Assign) -> Result<Literal, Unwind> {
+        let mut values = Vec::new();
+        for value in assign.values.iter() {
+            values.push(self.evaluate(value)?);
+        }
+
+        let result = values.last().cloned().unwrap_or(Literal::Null);
+        for (target, value) in assign.targets.iter().zip(values) {
+            self.assign(target, value)?;
+        }
+
+        Ok(result)
+    }
+
+    fn visit_array(&self, array: &Array) -> Result<Literal, Unwind> {
+        let mut elements = Vec::new();
+        for element in array.elements.iter() {
+            elements.push(self.evaluate(element)?);
+        }
+
+        Ok(Literal::Array(Rc::new(RefCell::new(elements))))
+    }
+
+    fn visit_index(&self, index: &Index) -> Result<Literal, Unwind> {
+        let object = self.evaluate(index.object.as_ref())?;
+        let position = self.evaluate(index.index.as_ref())?;
+
+        match object {
+            Literal::Array(array) => {
+                let array = array.borrow();
+                Ok(array[Interpreter::array_index(array.len(), position, &index.bracket)?].clone())
+            }
+            Literal::Instance(instance) => {
+                let name = Interpreter::field_name(position, &index.bracket)?;
+                instance.borrow().get(&name).ok_or_else(|| {
+                    RuntimeError::new(&index.bracket, format!("Undefined property '{}'.", name))
+                        .with_code("E0203")
+                        .into()
+                })
+            }
+            x => Err(type_error(&index.bracket, "Array or Instance", &x).into()),
+        }
+    }
+
+    fn visit_propagate(&self, propagate: &Propagate) -> Result<Literal, Unwind> {
+        let value = self.evaluate(propagate.expression.as_ref())?;
+
+        match &value {
+            Literal::Variant(x) if x.is_variant_of(&self.result_type, "Ok") => {
+                Ok(x.values[0].clone())
+            }
+            Literal::Variant(x) if x.is_variant_of(&self.result_type, "Err") => {
+                Err(Unwind::Return(value))
+            }
+            Literal::Variant(x) if x.variant_type.enum_name == "Result" => {
+                Err(type_error(&propagate.question, "Result", &value)
+                    .with_note(
+                        "Only the built in Result works with '?', not another enum called Result.",
+                    )
+                    .into())
+            }
+            x => Err(type_error(&propagate.question, "Result", x).into()),
+        }
+    }
+
+    // Only reached at the top level, awaits inside async functions are handled by resume_await.
+    fn visit_await(&self, await_expr: &Await) -> Result<Literal, Unwind> {
+        let task = self.awaited_task(await_expr)?;
+        self.block_on(&task, &await_expr.keyword)
+    }
+
+    fn visit_match(&self, match_expr: &Match) -> Result<Literal, Unwind> {
+        let value = self.evaluate(match_expr.value.as_ref())?;
+
+        for arm in match_expr.arms.iter() {
+            let mut bindings = Vec::new();
+            if !Interpreter::match_pattern(&arm.pattern, &value, &mut bindings) {
+                continue;
+            }
+
+            // Bindings are visible to the guard and the body, but nothing after the match.
+            let scope = self.with_bindings(bindings);
+
+            if let Some(guard) = &arm.guard {
+                if !Literal::is_truthy(&scope.evaluate(guard.as_ref())?) {
+                    continue;
+                }
+            }
+
+            return scope.evaluate(arm.body.as_ref());
+        }
+
+        Err(RuntimeError::new(
+            &match_expr.keyword,
+            format!("Non-exhaustive match, no arm matched {}.", value),
+        )
+        .with_code("E0208")
+        .into())
     }
 }
@@ -1,59 +1,715 @@
-use crate::error::{self, LogLevel};
+use crate::error::{Diagnostic, LogLevel};
 use crate::parser::expr::*;
-use crate::scanner::token::Token;
+use crate::parser::pattern::{ArrayPattern, Pattern, StructPattern, VariantPattern};
+use crate::parser::stmt::Stmt;
+use crate::scanner::scanner::Scanner;
+use crate::scanner::token::{SourceId, Span, Token};
 use crate::scanner::token_type::TokenType;
-use std::mem::discriminant;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::mem::{self, discriminant};
+use std::rc::Rc;
 
 pub mod ast_printer;
+pub mod clock;
+pub mod collections;
+pub mod enumeration;
+pub mod environment;
 pub mod expr;
+pub mod function;
+pub mod generator;
+pub mod instance;
+pub mod module;
+pub mod native;
+pub mod native_object;
+pub mod pattern;
+pub mod resume;
+pub mod stdlib;
 pub mod stmt;
 pub mod interpreter;
 pub mod runtime_error;
+pub mod task;
 
 struct ParseError;
 
+// A whole source, parsed. Warnings don't stop it running, so they come along with it.
+pub struct Program {
+    pub statements: Vec<Stmt>,
+    pub warnings: Vec<Diagnostic>,
+}
+
+// How many syntax errors the parser reports before it gives up on the rest of the file.
+pub const MAX_ERRORS: usize = 20;
+
+// Scans and parses the source. Any error means there's no program, just everything that was
+// found wrong with it, up to max_errors of them. Spans point into the source with this id.
+pub fn parse_source(
+    source: &str,
+    id: SourceId,
+    max_errors: usize,
+) -> Result<Program, Vec<Diagnostic>> {
+    let (tokens, scan_errors) = Scanner::new(source.to_string(), id).scan_tokens();
+    let mut parser = Parser::new(tokens);
+    parser.max_errors = max_errors;
+    parser.scan_errors = scan_errors;
+    parser.parse()
+}
+
 pub struct Parser {
-    source: String,
     tokens: Vec<Token>,
     pub current: u32,
+    // Variant names of every enum declared so far, for the exhaustiveness warning on match.
+    enums: HashMap<String, Vec<String>>,
+    // How many function bodies deep we are, `return` and `?` are errors at 0.
+    function_depth: u32,
+    // How many blocks deep we are, including function bodies. Imports and exports need 0.
+    block_depth: u32,
+    // What the innermost function is, `yield` and `await` are only allowed in some.
+    function_kind: stmt::FunctionKind,
+    // Errors and warnings so far, errors are also returned as a ParseError to unwind with.
+    diagnostics: RefCell<Vec<Diagnostic>>,
+    errors: usize,
+    pub max_errors: usize,
+    // What the scanner found wrong, reported along with the parser's own errors.
+    pub scan_errors: Vec<Diagnostic>,
 }
 
 impl Parser {
-    pub fn new(source: String, tokens: Vec<Token>) -> Parser {
+    pub fn new(tokens: Vec<Token>) -> Parser {
         Parser {
-            source,
             tokens,
             current: 0,
+            // The interpreter defines Result before any script runs.
+            enums: HashMap::from([(
+                "Result".to_string(),
+                vec!["Ok".to_string(), "Err".to_string()],
+            )]),
+            function_depth: 0,
+            block_depth: 0,
+            function_kind: stmt::FunctionKind::Function,
+            diagnostics: RefCell::new(Vec::new()),
+            errors: 0,
+            max_errors: MAX_ERRORS,
+            scan_errors: Vec::new(),
         }
     }
 
-    pub fn parse(&mut self) -> Option<Expr> {
-        if self.is_at_end() {
-            return None;
+    // Keeps going after an error from the next statement, so one run finds all of them.
+    pub fn parse(&mut self) -> Result<Program, Vec<Diagnostic>> {
+        let mut statements = Vec::new();
+        let mut gave_up = false;
+        while !self.is_at_end() {
+            let start = self.current;
+            match self.declaration() {
+                Ok(x) => statements.push(x),
+                Err(ParseError) => {
+                    if self.recover(start).is_err() {
+                        gave_up = !self.is_at_end();
+                        break;
+                    }
+                }
+            }
         }
-        match self.expression() {
-            Ok(x) => Some(x),
-            Err(_) => None, // wtf is this????
-            // TODO
+
+        let mut diagnostics = mem::take(self.diagnostics.get_mut());
+        diagnostics.append(&mut self.scan_errors);
+        // In the order they're in the source, whichever of the scanner and parser found them.
+        diagnostics.sort_by_key(|x| x.span.start);
+
+        match self.limit(&mut diagnostics, gave_up) {
+            0 => Ok(Program {
+                statements,
+                warnings: diagnostics,
+            }),
+            _ => Err(diagnostics),
         }
     }
 
-    fn expression(&mut self) -> Result<Expr, ParseError> {
-        self.comma()
+    // Keeps the first max_errors errors and what comes before the last of them, returning how
+    // many errors are left. If any were dropped, or the parser stopped early, the last one
+    // says so.
+    fn limit(&self, diagnostics: &mut Vec<Diagnostic>, gave_up: bool) -> usize {
+        let mut errors = 0;
+        let over = diagnostics.iter().position(|x| {
+            errors += x.is_error() as usize;
+            errors > self.max_errors
+        });
+        if let Some(over) = over {
+            diagnostics.truncate(over);
+        }
+
+        let last = diagnostics.iter_mut().rev().find(|x| x.is_error());
+        if let Some(last) = last.filter(|_| over.is_some() || gave_up) {
+            last.notes.push(format!(
+                "Stopped after {} errors, the rest of the file wasn't checked.",
+                self.max_errors
+            ));
+        }
+        errors.min(self.max_errors)
+    }
+
+    // Counts the error, then skips to the next statement unless there have been too many.
+    // Blocks do this for their own statements, so an error inside one doesn't end it early.
+    fn recover(&mut self, start: u32) -> Result<(), ParseError> {
+        self.errors += 1;
+        if self.errors >= self.max_errors {
+            return Err(ParseError);
+        }
+        self.synchronise(start);
+        Ok(())
     }
 
-    fn comma(&mut self) -> Result<Expr, ParseError> {
-        let mut expr = self.ternary();
+    fn declaration(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.peek(0).span();
+        if self.cmp(&[TokenType::Export]) {
+            return self.export_declaration();
+        }
+        if self.cmp(&[TokenType::Let]) {
+            return self.let_declaration();
+        }
+        if self.cmp(&[TokenType::Struct]) {
+            return self.struct_declaration();
+        }
+        if self.cmp(&[TokenType::Enum]) {
+            return self.enum_declaration();
+        }
+        if self.cmp(&[TokenType::Fn]) {
+            let kind = match self.cmp(&[TokenType::Star]) {
+                true => stmt::FunctionKind::Generator,
+                false => stmt::FunctionKind::Function,
+            };
+            return self.function_declaration(kind, start);
+        }
+        if self.cmp(&[TokenType::Async]) {
+            self.consume(TokenType::Fn, "Expect 'fn' after 'async'.")?;
+            if self.check(&TokenType::Star) {
+                return Err(self.error(self.peek(0), "Async generators aren't supported."));
+            }
+            return self.function_declaration(stmt::FunctionKind::Async, start);
+        }
+
+        self.statement()
+    }
 
+    fn export_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.peek(-1).clone();
+        if self.block_depth > 0 {
+            return Err(self.error(&keyword, "Can only export from the top level of a module."));
+        }
+
+        let declarations = [
+            TokenType::Let,
+            TokenType::Fn,
+            TokenType::Async,
+            TokenType::Struct,
+            TokenType::Enum,
+        ];
+        if !declarations.iter().any(|x| self.check(x)) {
+            return Err(self.error(
+                self.peek(0),
+                "Expect a let, fn, struct or enum declaration after 'export'.",
+            ));
+        }
+
+        let declaration = self.declaration()?;
+        Ok(Stmt::Export(stmt::Export {
+            span: keyword.span().to(declaration.span()),
+            keyword,
+            declaration: Box::new(declaration),
+        }))
+    }
+
+    fn let_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.peek(-1).clone();
+
+        let mut patterns = vec![self.pattern()?];
         while self.cmp(&[TokenType::Comma]) {
-            let next = self.ternary();
-            expr = Ok(Expr::Comma(Comma {
-                expr: Box::new(expr?),
-                next: Box::new(next?),
+            patterns.push(self.pattern()?);
+        }
+
+        let mut initialisers = Vec::new();
+        if self.cmp(&[TokenType::Equal]) {
+            let equals = self.peek(-1).clone();
+            initialisers = match self.cmp(&[TokenType::Await]) {
+                true => vec![self.await_expression()?],
+                false => self.comma_list()?,
+            };
+
+            if initialisers.len() != patterns.len() {
+                return Err(self.error(
+                    &equals,
+                    &format!(
+                        "Expected {} values after '=' but got {}.",
+                        patterns.len(),
+                        initialisers.len()
+                    ),
+                ));
+            }
+        }
+
+        Ok(Stmt::Let(stmt::Let {
+            span: self.span_from(keyword.span()),
+            keyword,
+            patterns,
+            initialisers,
+        }))
+    }
+
+    fn struct_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.peek(-1).span();
+        let name = self
+            .consume(TokenType::Identifier, "Expect struct name.")?
+            .clone();
+        self.consume(TokenType::LeftBrace, "Expect '{' before struct body.")?;
+
+        let mut fields = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            fields.push(
+                self.consume(TokenType::Identifier, "Expect field name.")?
+                    .clone(),
+            );
+            // Commas between fields are optional, just like everywhere else.
+            self.cmp(&[TokenType::Comma]);
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after struct body.")?;
+        Ok(Stmt::Struct(stmt::Struct {
+            name,
+            fields,
+            span: self.span_from(keyword),
+        }))
+    }
+
+    fn enum_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.peek(-1).span();
+        let name = self
+            .consume(TokenType::Identifier, "Expect enum name.")?
+            .clone();
+        self.consume(TokenType::LeftBrace, "Expect '{' before enum body.")?;
+
+        let mut variants = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            let variant = self
+                .consume(TokenType::Identifier, "Expect variant name.")?
+                .clone();
+
+            let mut fields = Vec::new();
+            if self.cmp(&[TokenType::LeftParen]) {
+                loop {
+                    fields.push(
+                        self.consume(TokenType::Identifier, "Expect variant field name.")?
+                            .clone(),
+                    );
+                    if !self.cmp(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+                self.consume(TokenType::RightParen, "Expect ')' after variant fields.")?;
+            }
+
+            variants.push(stmt::EnumVariant {
+                name: variant,
+                fields,
+            });
+            self.cmp(&[TokenType::Comma]);
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after enum body.")?;
+
+        self.enums.insert(
+            name.lexeme.clone(),
+            variants.iter().map(|x| x.name.lexeme.clone()).collect(),
+        );
+        Ok(Stmt::Enum(stmt::Enum {
+            name,
+            variants,
+            span: self.span_from(keyword),
+        }))
+    }
+
+    // Starts after the `fn`, `fn*` or `async fn`, which start is the start of.
+    fn function_declaration(
+        &mut self,
+        kind: stmt::FunctionKind,
+        start: Span,
+    ) -> Result<Stmt, ParseError> {
+        let name = self
+            .consume(TokenType::Identifier, "Expect function name.")?
+            .clone();
+        self.consume(TokenType::LeftParen, "Expect '(' after function name.")?;
+
+        let mut params = Vec::new();
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                params.push(
+                    self.consume(TokenType::Identifier, "Expect parameter name.")?
+                        .clone(),
+                );
+                if !self.cmp(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before function body.")?;
+
+        let enclosing_kind = self.function_kind;
+        self.function_kind = kind;
+        self.function_depth += 1;
+        let body = self.block();
+        self.function_depth -= 1;
+        self.function_kind = enclosing_kind;
+
+        Ok(Stmt::Function(Rc::new(stmt::Function {
+            name,
+            params,
+            body: body?,
+            kind,
+            span: self.span_from(start),
+        })))
+    }
+
+    fn statement(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.peek(0).span();
+        if self.cmp(&[TokenType::LeftBrace]) {
+            return Ok(Stmt::Block(stmt::Block {
+                statements: self.block()?,
+                span: self.span_from(start),
+            }));
+        }
+        if self.cmp(&[TokenType::Print]) {
+            let expression = match self.cmp(&[TokenType::Await]) {
+                true => self.await_expression()?,
+                false => self.expression()?,
+            };
+            return Ok(Stmt::Print(stmt::Print {
+                expression,
+                span: self.span_from(start),
+            }));
+        }
+        if self.cmp(&[TokenType::Throw]) {
+            let keyword = self.peek(-1).clone();
+            return Ok(Stmt::Throw(stmt::Throw {
+                keyword,
+                value: self.expression()?,
+                span: self.span_from(start),
+            }));
+        }
+        if self.cmp(&[TokenType::Try]) {
+            return self.try_statement();
+        }
+        if self.cmp(&[TokenType::Return]) {
+            return self.return_statement();
+        }
+        if self.cmp(&[TokenType::If]) {
+            return self.if_statement();
+        }
+        if self.cmp(&[TokenType::While]) {
+            let condition = self.expression()?;
+            return Ok(Stmt::While(stmt::While {
+                condition,
+                body: self.body("Expect '{' after while condition.")?,
+                span: self.span_from(start),
+            }));
+        }
+        if self.cmp(&[TokenType::For]) {
+            return self.for_statement();
+        }
+        if self.cmp(&[TokenType::Yield]) {
+            return self.yield_statement();
+        }
+        if self.cmp(&[TokenType::Import]) {
+            return self.import_statement();
+        }
+        if self.cmp(&[TokenType::Await]) {
+            return Ok(Stmt::Expression(stmt::Expression {
+                expression: self.await_expression()?,
+                span: self.span_from(start),
             }));
         }
 
-        expr
+        let expression = self.expression()?;
+        Ok(Stmt::Expression(stmt::Expression {
+            span: expression.span(),
+            expression,
+        }))
+    }
+
+    fn return_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.peek(-1).clone();
+        if self.function_depth == 0 {
+            return Err(self.error(&keyword, "Can't return from top-level code."));
+        }
+
+        // There's no semicolon to look for, so a value has to start on the same line.
+        let mut value = None;
+        if !self.check(&TokenType::RightBrace) && self.peek(0).line == keyword.line {
+            value = match self.cmp(&[TokenType::Await]) {
+                true => Some(self.await_expression()?),
+                false => Some(self.expression()?),
+            };
+        }
+
+        Ok(Stmt::Return(stmt::Return {
+            span: self.span_from(keyword.span()),
+            keyword,
+            value,
+        }))
+    }
+
+    fn if_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.peek(-1).span();
+        let condition = self.expression()?;
+        let then_branch = self.body("Expect '{' after if condition.")?;
+
+        let mut else_branch = None;
+        if self.cmp(&[TokenType::Else]) {
+            else_branch = match self.cmp(&[TokenType::If]) {
+                true => Some(vec![self.if_statement()?]),
+                false => Some(self.body("Expect '{' after else.")?),
+            };
+        }
+
+        Ok(Stmt::If(stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+            span: self.span_from(keyword),
+        }))
+    }
+
+    fn for_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.peek(-1).clone();
+        let pattern = self.pattern()?;
+        self.consume(TokenType::In, "Expect 'in' after for loop pattern.")?;
+        let iterable = self.expression()?;
+
+        let body = self.body("Expect '{' after for loop iterable.")?;
+        Ok(Stmt::For(Box::new(stmt::For {
+            span: self.span_from(keyword.span()),
+            keyword,
+            pattern,
+            iterable,
+            body,
+        })))
+    }
+
+    fn yield_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.peek(-1).clone();
+        if self.function_kind != stmt::FunctionKind::Generator {
+            return Err(self.error(&keyword, "Can only yield inside a generator, declare it with 'fn*'."));
+        }
+
+        // Same as return, the value has to start on the same line.
+        let mut value = None;
+        if !self.check(&TokenType::RightBrace) && self.peek(0).line == keyword.line {
+            value = Some(self.expression()?);
+        }
+
+        Ok(Stmt::Yield(stmt::Yield {
+            span: self.span_from(keyword.span()),
+            keyword,
+            value,
+        }))
+    }
+
+    // `import "path"` or `import { a, b } from "path"`, `from` is only special here.
+    fn import_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.peek(-1).clone();
+        if self.block_depth > 0 {
+            return Err(self.error(&keyword, "Can only import at the top level of a module."));
+        }
+
+        let mut names = None;
+        if self.cmp(&[TokenType::LeftBrace]) {
+            let mut list = Vec::new();
+            while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+                list.push(
+                    self.consume(TokenType::Identifier, "Expect name to import.")?
+                        .clone(),
+                );
+                if !self.cmp(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightBrace, "Expect '}' after imported names.")?;
+
+            if !(self.check(&TokenType::Identifier) && self.peek(0).lexeme == "from") {
+                return Err(self.error(self.peek(0), "Expect 'from' after imported names."));
+            }
+            self.advance();
+            names = Some(list);
+        }
+
+        let path = match &self.advance().token {
+            TokenType::String(x) => x.clone(),
+            _ => return Err(self.error(self.peek(-1), "Expect module path string.")),
+        };
+
+        Ok(Stmt::Import(stmt::Import {
+            span: self.span_from(keyword.span()),
+            keyword,
+            names,
+            path,
+        }))
+    }
+
+    // Tasks can only stop and wait between statements, so `await` has to be the whole of an
+    // expression statement, a let's value, a return value or what's printed.
+    // Expects the 'await' to be consumed.
+    fn await_expression(&mut self) -> Result<Expr, ParseError> {
+        let keyword = self.peek(-1).clone();
+        if self.function_depth > 0 && self.function_kind != stmt::FunctionKind::Async {
+            return Err(self.error(
+                &keyword,
+                "Can only await inside an async fn or at the top level.",
+            ));
+        }
+
+        let value = self.ternary()?;
+        Ok(Expr::Await(Await {
+            span: keyword.span().to(value.span()),
+            keyword,
+            value: Box::new(value),
+        }))
+    }
+
+    // The braces around if, else, while and for bodies aren't optional.
+    fn body(&mut self, message: &str) -> Result<Vec<Stmt>, ParseError> {
+        self.consume(TokenType::LeftBrace, message)?;
+        self.block()
+    }
+
+    // Expects the '{' to have been consumed already.
+    fn block(&mut self) -> Result<Vec<Stmt>, ParseError> {
+        self.block_depth += 1;
+        let statements = self.block_statements();
+        self.block_depth -= 1;
+        statements
+    }
+
+    fn block_statements(&mut self) -> Result<Vec<Stmt>, ParseError> {
+        let mut statements = Vec::new();
+
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            let start = self.current;
+            match self.declaration() {
+                Ok(x) => statements.push(x),
+                Err(ParseError) => self.recover(start)?,
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
+        Ok(statements)
+    }
+
+    fn try_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.peek(-1).clone();
+        self.consume(TokenType::LeftBrace, "Expect '{' after 'try'.")?;
+        let try_block = self.block()?;
+
+        let mut catch_name = None;
+        let mut catch_block = None;
+        if self.cmp(&[TokenType::Catch]) {
+            if self.cmp(&[TokenType::LeftParen]) {
+                catch_name = Some(
+                    self.consume(TokenType::Identifier, "Expect name of caught value.")?
+                        .clone(),
+                );
+                self.consume(TokenType::RightParen, "Expect ')' after caught value name.")?;
+            }
+            self.consume(TokenType::LeftBrace, "Expect '{' after 'catch'.")?;
+            catch_block = Some(self.block()?);
+        }
+
+        let mut finally_block = None;
+        if self.cmp(&[TokenType::Finally]) {
+            self.consume(TokenType::LeftBrace, "Expect '{' after 'finally'.")?;
+            finally_block = Some(self.block()?);
+        }
+
+        if catch_block.is_none() && finally_block.is_none() {
+            return Err(self.error(&keyword, "Expect 'catch' or 'finally' after try block."));
+        }
+
+        Ok(Stmt::Try(stmt::Try {
+            try_block,
+            catch_name,
+            catch_block,
+            finally_block,
+            span: self.span_from(keyword.span()),
+        }))
+    }
+
+    fn expression(&mut self) -> Result<Expr, ParseError> {
+        self.assignment()
+    }
+
+    // Commas bind tighter than '=', so `a, b = b, a` is one assignment to two targets.
+    // Without an '=' afterwards the list is just the comma operator.
+    // Anywhere commas are separators (arguments, array elements, match arms) parsing starts at ternary instead.
+    fn assignment(&mut self) -> Result<Expr, ParseError> {
+        let exprs = self.comma_list()?;
+
+        if self.cmp(&[TokenType::Equal]) {
+            return self.finish_assignment(exprs);
+        }
+
+        let mut exprs = exprs.into_iter();
+        let mut expr = exprs.next().unwrap();
+        for next in exprs {
+            expr = Expr::Comma(Comma {
+                span: expr.span().to(next.span()),
+                expr: Box::new(expr),
+                next: Box::new(next),
+            });
+        }
+
+        Ok(expr)
+    }
+
+    fn finish_assignment(&mut self, targets: Vec<Expr>) -> Result<Expr, ParseError> {
+        let equals = self.peek(-1).clone();
+        let mut values = self.comma_list()?;
+
+        // `a = b = c` assigns right to left.
+        if self.cmp(&[TokenType::Equal]) {
+            values = vec![self.finish_assignment(values)?];
+        }
+
+        for target in &targets {
+            if !matches!(target, Expr::Variable(_) | Expr::Get(_) | Expr::Index(_)) {
+                return Err(self.error(&equals, "Invalid assignment target."));
+            }
+        }
+
+        if values.len() != targets.len() {
+            return Err(self.error(
+                &equals,
+                &format!(
+                    "Expected {} values after '=' but got {}.",
+                    targets.len(),
+                    values.len()
+                ),
+            ));
+        }
+
+        Ok(Expr::Assign(Assign {
+            span: targets[0].span().to(values[values.len() - 1].span()),
+            targets,
+            equals,
+            values,
+        }))
+    }
+
+    fn comma_list(&mut self) -> Result<Vec<Expr>, ParseError> {
+        let mut exprs = vec![self.ternary()?];
+
+        while self.cmp(&[TokenType::Comma]) {
+            exprs.push(self.ternary()?);
+        }
+
+        Ok(exprs)
     }
 
     fn ternary(&mut self) -> Result<Expr, ParseError> {
@@ -67,10 +723,13 @@ impl Parser {
                 "Expect ':' after then branch of conditional expression.",
             )?;
 
-            let if_false = self.expression()?;
+            // Not expression(), or `a ? b : c, d` would swallow the `, d`.
+            let if_false = self.ternary()?;
 
+            let condition_expr = condition?;
             condition = Ok(Expr::Ternary(Ternary {
-                condition: Box::new(condition?),
+                span: condition_expr.span().to(if_false.span()),
+                condition: Box::new(condition_expr),
                 if_true: Box::new(if_true),
                 if_false: Box::new(if_false),
             }))
@@ -84,11 +743,13 @@ impl Parser {
 
         while self.cmp(&[TokenType::BangEqual, TokenType::EqualEqual]) {
             let operator = self.peek(-1).clone();
-            let right = self.comparison();
+            let left = expr?;
+            let right = self.comparison()?;
             expr = Ok(Expr::Binary(Binary {
-                left: Box::new(expr?),
+                span: left.span().to(right.span()),
+                left: Box::new(left),
                 operator: operator.clone(),
-                right: Box::new(right?),
+                right: Box::new(right),
             }));
         }
 
@@ -103,13 +764,16 @@ impl Parser {
             TokenType::GreaterEqual,
             TokenType::Less,
             TokenType::LessEqual,
+            TokenType::Is,
         ]) {
             let operator = self.peek(-1).clone();
-            let right = self.term();
+            let left = expr?;
+            let right = self.term()?;
             expr = Ok(Expr::Binary(Binary {
-                left: Box::new(expr?),
+                span: left.span().to(right.span()),
+                left: Box::new(left),
                 operator: operator.clone(),
-                right: Box::new(right?),
+                right: Box::new(right),
             }));
         }
 
@@ -121,11 +785,13 @@ impl Parser {
 
         while self.cmp(&[TokenType::Minus, TokenType::Plus]) {
             let operator = self.peek(-1).clone();
-            let right = self.factor();
+            let left = expr?;
+            let right = self.factor()?;
             expr = Ok(Expr::Binary(Binary {
-                left: Box::new(expr?),
+                span: left.span().to(right.span()),
+                left: Box::new(left),
                 operator: operator.clone(),
-                right: Box::new(right?),
+                right: Box::new(right),
             }))
         }
 
@@ -137,11 +803,13 @@ impl Parser {
 
         while self.cmp(&[TokenType::Slash, TokenType::Star]) {
             let operator = self.peek(-1).clone();
-            let right = self.unary();
+            let left = expr?;
+            let right = self.unary()?;
             expr = Ok(Expr::Binary(Binary {
-                left: Box::new(expr?),
+                span: left.span().to(right.span()),
+                left: Box::new(left),
                 operator: operator.clone(),
-                right: Box::new(right?),
+                right: Box::new(right),
             }))
         }
 
@@ -151,32 +819,361 @@ impl Parser {
     fn unary(&mut self) -> Result<Expr, ParseError> {
         if self.cmp(&[TokenType::Bang, TokenType::Minus]) {
             let operator = self.peek(-1).clone();
-            let expression = self.unary();
+            let expression = self.unary()?;
             return Ok(Expr::Unary(Unary {
+                span: operator.span().to(expression.span()),
                 operator,
-                expression: Box::new(expression?),
+                expression: Box::new(expression),
             }));
         }
 
-        self.primary()
+        self.call()
+    }
+
+    fn call(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.primary()?;
+
+        loop {
+            // A paren or bracket on the next line starts a new expression rather than continuing this one.
+            if self.check(&TokenType::LeftParen) && self.peek(0).line == self.peek(-1).line {
+                self.advance();
+                expr = self.finish_call(expr)?;
+            } else if self.check(&TokenType::LeftBracket) && self.peek(0).line == self.peek(-1).line {
+                let bracket = self.advance().clone();
+                let index = self.ternary()?;
+                self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                expr = Expr::Index(Index {
+                    span: self.span_from(expr.span()),
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                });
+            } else if self.check(&TokenType::Question) && self.is_postfix_question() {
+                let question = self.advance().clone();
+                if self.function_depth == 0 {
+                    return Err(self.error(&question, "Can't use '?' outside of a function."));
+                }
+                expr = Expr::Propagate(Propagate {
+                    span: expr.span().to(question.span()),
+                    expression: Box::new(expr),
+                    question,
+                });
+            } else if self.cmp(&[TokenType::Dot]) {
+                let name = self
+                    .consume(TokenType::Identifier, "Expect property name after '.'.")?
+                    .clone();
+                expr = Expr::Get(Get {
+                    span: expr.span().to(name.span()),
+                    object: Box::new(expr),
+                    name,
+                });
+            } else {
+                break;
+            }
+        }
+
+        Ok(expr)
+    }
+
+    // `a ? b : c` and `a?` both start with a '?'. It's the ternary if an expression follows on the same line,
+    // so `f()? - 1` needs parentheses: `(f()?) - 1`.
+    fn is_postfix_question(&self) -> bool {
+        let next = self.peek(1);
+
+        next.line != self.peek(0).line
+            || !matches!(
+                next.token,
+                TokenType::Number(_)
+                    | TokenType::String(_)
+                    | TokenType::Identifier
+                    | TokenType::True
+                    | TokenType::False
+                    | TokenType::Null
+                    | TokenType::LeftParen
+                    | TokenType::LeftBracket
+                    | TokenType::Bang
+                    | TokenType::Minus
+                    | TokenType::Match
+            )
+    }
+
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr, ParseError> {
+        let mut arguments = Vec::new();
+
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                // Arguments are parsed below the comma operator, otherwise `f(a, b)` would be `f((a, b))`.
+                arguments.push(self.ternary()?);
+                if !self.cmp(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        let paren = self
+            .consume(TokenType::RightParen, "Expect ')' after arguments.")?
+            .clone();
+
+        Ok(Expr::Call(Call {
+            span: callee.span().to(paren.span()),
+            callee: Box::new(callee),
+            paren,
+            arguments,
+        }))
     }
 
     fn primary(&mut self) -> Result<Expr, ParseError> {
+        // advance() stays put at the end, and would hand back the token before it again.
+        if self.is_at_end() {
+            return Err(self.error(self.peek(0), "Expect expression."));
+        }
+        let start = self.current;
+        let span = self.peek(0).span();
         match &self.advance().token {
-            TokenType::False => Ok(Expr::Literal(Literal::False)),
-            TokenType::True => Ok(Expr::Literal(Literal::True)),
-            TokenType::Null => Ok(Expr::Literal(Literal::Null)),
-            TokenType::Number(x) => Ok(Expr::Literal(Literal::Number(*x))),
-            TokenType::String(x) => Ok(Expr::Literal(Literal::String(x.clone()))),
+            TokenType::False => Ok(Expr::Literal(Literal::False, span)),
+            TokenType::True => Ok(Expr::Literal(Literal::True, span)),
+            TokenType::Null => Ok(Expr::Literal(Literal::Null, span)),
+            TokenType::Number(x) => Ok(Expr::Literal(Literal::Number(*x), span)),
+            TokenType::String(x) => Ok(Expr::Literal(Literal::String(x.clone()), span)),
+            TokenType::Identifier => Ok(Expr::Variable(Variable {
+                name: self.peek(-1).clone(),
+                span,
+            })),
+            TokenType::Match => self.match_expression(),
+            TokenType::Await => Err(self.error(
+                self.peek(-1),
+                "'await' can only start a statement, or come straight after 'print', 'return' or a let's '='.",
+            )),
+            TokenType::LeftBracket => {
+                let mut elements = Vec::new();
+                while !self.check(&TokenType::RightBracket) && !self.is_at_end() {
+                    elements.push(self.ternary()?);
+                    if !self.cmp(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+                self.consume(TokenType::RightBracket, "Expect ']' after array elements.")?;
+                Ok(Expr::Array(Array {
+                    elements,
+                    span: self.span_from(span),
+                }))
+            }
             TokenType::LeftParen => {
-                let expr = self.expression();
+                let expr = self.expression()?;
                 self.consume(TokenType::RightParen, "Expected ')' after expression.")?;
                 Ok(Expr::Grouping(Grouping {
-                    expression: Box::new(expr?),
+                    expression: Box::new(expr),
+                    span: self.span_from(span),
                 }))
             }
-            _ => Err(self.error(self.peek(0), "Expect expression.")),
+            // Left unconsumed, it might be what starts the next statement.
+            _ => {
+                self.current = start;
+                Err(self.error(self.peek(0), "Expect expression."))
+            }
+        }
+    }
+
+    fn match_expression(&mut self) -> Result<Expr, ParseError> {
+        let keyword = self.peek(-1).clone();
+        let value = self.ternary()?;
+        self.consume(TokenType::LeftBrace, "Expect '{' after match value.")?;
+
+        let mut arms = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            let pattern = self.pattern()?;
+
+            let mut guard = None;
+            if self.cmp(&[TokenType::If]) {
+                guard = Some(Box::new(self.ternary()?));
+            }
+
+            self.consume(TokenType::FatArrow, "Expect '=>' after match pattern.")?;
+            let body = self.ternary()?;
+            self.cmp(&[TokenType::Comma]);
+
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body: Box::new(body),
+            });
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after match arms.")?;
+
+        if !self.is_exhaustive(&arms) {
+            self.warning(&keyword, "Match may not be exhaustive.");
+            if let Some(x) = self.diagnostics.borrow_mut().last_mut() {
+                x.help.push("Add a '_' arm to match anything else.".to_string());
+            }
+        }
+
+        Ok(Expr::Match(Match {
+            span: self.span_from(keyword.span()),
+            keyword,
+            value: Box::new(value),
+            arms,
+        }))
+    }
+
+    // This is only a best guess without knowing the type of the value being matched.
+    // Anything it can't prove is exhaustive gets a warning, and the interpreter errors at runtime.
+    fn is_exhaustive(&self, arms: &[MatchArm]) -> bool {
+        let unguarded = || arms.iter().filter(|x| x.guard.is_none());
+
+        let catch_all = unguarded().any(|x| x.pattern.is_irrefutable());
+        let covers_bools = [Literal::True, Literal::False].iter().all(|bool| {
+            unguarded().any(|x| matches!(&x.pattern, Pattern::Literal(literal) if literal == bool))
+        });
+        let covers_enum = self.enums.iter().any(|(enum_name, variants)| {
+            variants.iter().all(|variant| {
+                unguarded().any(|x| match &x.pattern {
+                    Pattern::Variant(pattern) => {
+                        pattern.name.lexeme == *variant
+                            && pattern
+                                .enum_name
+                                .as_ref()
+                                .is_none_or(|x| x.lexeme == *enum_name)
+                            && pattern
+                                .fields
+                                .as_ref()
+                                .is_none_or(|x| x.iter().all(|x| x.is_irrefutable()))
+                    }
+                    _ => false,
+                })
+            })
+        });
+
+        catch_all || covers_bools || covers_enum
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, ParseError> {
+        if self.cmp(&[TokenType::Identifier]) {
+            let name = self.peek(-1).clone();
+
+            if name.lexeme == "_" {
+                return Ok(Pattern::Wildcard);
+            }
+            if self.cmp(&[TokenType::LeftBrace]) {
+                return self.struct_pattern(Some(name));
+            }
+            if self.cmp(&[TokenType::Dot]) {
+                let variant = self
+                    .consume(TokenType::Identifier, "Expect variant name after '.'.")?
+                    .clone();
+                return self.variant_pattern(Some(name), variant);
+            }
+            if self.check(&TokenType::LeftParen) {
+                return self.variant_pattern(None, name);
+            }
+            return Ok(Pattern::Binding(name));
+        }
+
+        if self.cmp(&[TokenType::LeftBrace]) {
+            return self.struct_pattern(None);
+        }
+        if self.cmp(&[TokenType::LeftBracket]) {
+            return self.array_pattern();
+        }
+
+        let start = self.pattern_literal()?;
+        if self.cmp(&[TokenType::DotDot, TokenType::DotDotEqual]) {
+            let inclusive = self.peek(-1).token == TokenType::DotDotEqual;
+            let end = self.pattern_literal()?;
+            return Ok(Pattern::Range(pattern::Range {
+                start,
+                end,
+                inclusive,
+            }));
+        }
+
+        Ok(Pattern::Literal(start))
+    }
+
+    fn pattern_literal(&mut self) -> Result<Literal, ParseError> {
+        let negative = self.cmp(&[TokenType::Minus]);
+
+        match &self.advance().token {
+            TokenType::Number(x) if negative => Ok(Literal::Number(-*x)),
+            TokenType::Number(x) => Ok(Literal::Number(*x)),
+            TokenType::String(x) if !negative => Ok(Literal::String(x.clone())),
+            TokenType::True if !negative => Ok(Literal::True),
+            TokenType::False if !negative => Ok(Literal::False),
+            TokenType::Null if !negative => Ok(Literal::Null),
+            _ => Err(self.error(self.peek(-1), "Expect pattern.")),
+        }
+    }
+
+    fn struct_pattern(&mut self, name: Option<Token>) -> Result<Pattern, ParseError> {
+        let mut fields = Vec::new();
+
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            let field = self
+                .consume(TokenType::Identifier, "Expect field name in struct pattern.")?
+                .clone();
+
+            // `Point { x }` is shorthand for `Point { x: x }`.
+            let pattern = match self.cmp(&[TokenType::Colon]) {
+                true => self.pattern()?,
+                false => Pattern::Binding(field.clone()),
+            };
+            fields.push((field, pattern));
+
+            if !self.cmp(&[TokenType::Comma]) {
+                break;
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after struct pattern.")?;
+        Ok(Pattern::Struct(StructPattern { name, fields }))
+    }
+
+    fn array_pattern(&mut self) -> Result<Pattern, ParseError> {
+        let mut elements = Vec::new();
+        let mut rest = None;
+
+        while !self.check(&TokenType::RightBracket) && !self.is_at_end() {
+            if self.cmp(&[TokenType::DotDotDot]) {
+                // A bare `...` ignores the rest without binding it.
+                rest = match self.check(&TokenType::Identifier) {
+                    true => Some(Box::new(self.pattern()?)),
+                    false => Some(Box::new(Pattern::Wildcard)),
+                };
+                break;
+            }
+
+            elements.push(self.pattern()?);
+            if !self.cmp(&[TokenType::Comma]) {
+                break;
+            }
         }
+
+        self.consume(TokenType::RightBracket, "Expect ']' after array pattern.")?;
+        Ok(Pattern::Array(ArrayPattern { elements, rest }))
+    }
+
+    fn variant_pattern(&mut self, enum_name: Option<Token>, name: Token) -> Result<Pattern, ParseError> {
+        let mut fields = None;
+
+        if self.cmp(&[TokenType::LeftParen]) {
+            let mut patterns = Vec::new();
+            if !self.check(&TokenType::RightParen) {
+                loop {
+                    patterns.push(self.pattern()?);
+                    if !self.cmp(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightParen, "Expect ')' after variant pattern.")?;
+            fields = Some(patterns);
+        }
+
+        Ok(Pattern::Variant(VariantPattern {
+            enum_name,
+            name,
+            fields,
+        }))
     }
 
     // Helpers
@@ -204,10 +1201,15 @@ impl Parser {
         self.peek(-1)
     }
 
-    fn is_at_end(&self) -> bool {
+    pub fn is_at_end(&self) -> bool {
         self.current as usize == self.tokens.len() - 1
     }
 
+    // From start to the end of the last token consumed.
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.peek(-1).span())
+    }
+
     fn peek(&self, offset: i32) -> &Token {
         self.tokens
             .get((self.current as i32 + offset) as usize)
@@ -223,53 +1225,85 @@ impl Parser {
     }
 
     fn error(&self, token: &Token, message: &str) -> ParseError {
-        if token.token != TokenType::Eof {
-            error::report(
-                token.line,
-                token.column,
-                LogLevel::Error,
-                &format!("at '{}'", token.lexeme),
-                message,
-                &self.source,
-            );
-        } else {
-            error::report(
-                token.line,
-                token.column,
-                LogLevel::Error,
-                "at end",
-                message,
-                &self.source,
-            );
-        }
-
+        self.report(token, LogLevel::Error, message);
         ParseError {}
     }
 
-    #[allow(dead_code)]
-    // I don't know what this is anymore or what it does but I am just going to leave it here for now.
-    fn synchronise(&mut self) {
-        self.advance();
+    fn warning(&self, token: &Token, message: &str) {
+        self.report(token, LogLevel::Warning, message);
+    }
+
+    fn report(&self, token: &Token, log_level: LogLevel, message: &str) {
+        let code = match log_level {
+            LogLevel::Warning => "W0100",
+            _ => "E0100",
+        };
+        self.diagnostics
+            .borrow_mut()
+            .push(Diagnostic::new(log_level, code, message, token.span()));
+    }
+
+    // Skips what's left of a statement with an error in it, up to the start of the next one.
+    // That's the next line, or a keyword that starts one, like the `let` in `x = 1 + let y = 2`.
+    // Anything inside braces the statement opened is part of it, and a `}` it didn't open is
+    // the end of the block it's in, which is left for the block to close.
+    // It always moves at least one token past where the statement started so it can't get stuck.
+    fn synchronise(&mut self, start: u32) {
+        if self.current == start {
+            self.advance();
+        }
+
+        let mut depth = 0;
+        for token in self.tokens[start as usize..self.current as usize].iter() {
+            depth = Parser::brace_depth(depth, &token.token);
+        }
 
         while !self.is_at_end() {
-            if self.peek(-1).token == TokenType::Semicolon {
-                return;
+            if depth == 0 {
+                if self.peek(-1).token == TokenType::Semicolon
+                    || self.peek(0).line > self.peek(-1).line
+                    || self.starts_statement()
+                {
+                    return;
+                }
+                if self.check(&TokenType::RightBrace) && self.block_depth > 0 {
+                    return;
+                }
             }
 
-            match self.peek(0).token {
-                TokenType::Class
+            depth = Parser::brace_depth(depth, &self.advance().token);
+        }
+    }
+
+    // How many braces are open after this token. Closing one that was never opened doesn't
+    // count, that's a stray `}` at the top level.
+    fn brace_depth(depth: u32, token: &TokenType) -> u32 {
+        match token {
+            TokenType::LeftBrace => depth + 1,
+            TokenType::RightBrace => depth.saturating_sub(1),
+            _ => depth,
+        }
+    }
+
+    fn starts_statement(&self) -> bool {
+        matches!(
+            self.peek(0).token,
+            TokenType::Class
+                | TokenType::Struct
+                | TokenType::Enum
                 | TokenType::Fn
+                | TokenType::Async
                 | TokenType::Let
+                | TokenType::Import
+                | TokenType::Export
                 | TokenType::For
                 | TokenType::If
                 | TokenType::While
                 | TokenType::Print
-                | TokenType::Return => return,
-
-                _ => {}
-            }
-
-            self.advance();
-        }
+                | TokenType::Throw
+                | TokenType::Try
+                | TokenType::Yield
+                | TokenType::Return
+        )
     }
 }
@@ -0,0 +1,355 @@
+use super::environment::Environment;
+use super::expr::Literal;
+use super::interpreter::Interpreter;
+use super::runtime_error::{RuntimeError, Unwind};
+use super::stmt;
+use crate::error::{Diagnostic, Error};
+use crate::manifest;
+use crate::parser;
+use crate::scanner::token::SourceId;
+use crate::scanner::token::Token;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// An imported file. Exports are read out of its environment when they're used,
+// so they see any changes the module makes to them later.
+pub struct Module {
+    pub name: String,
+    environment: Rc<RefCell<Environment>>,
+    exports: Vec<String>,
+}
+
+impl Module {
+    // A module written in Rust, everything in it is exported.
+    pub fn native(name: &str, members: Vec<(String, Literal)>) -> Module {
+        let mut environment = Environment::new(None);
+        let mut exports = Vec::new();
+        for (name, value) in members {
+            exports.push(name.clone());
+            environment.define(name, value);
+        }
+
+        Module {
+            name: name.to_string(),
+            environment: Rc::new(RefCell::new(environment)),
+            exports,
+        }
+    }
+
+    pub fn get(&self, name: &Token) -> Option<Literal> {
+        if !self.exports.contains(&name.lexeme) {
+            return None;
+        }
+        self.environment.borrow().get(name).ok()
+    }
+}
+
+// Every module loaded so far, keyed by canonical path so each file only ever runs once.
+pub struct Modules {
+    cache: HashMap<PathBuf, Rc<Module>>,
+    // The files part way through running, the innermost last. Importing one of these is a cycle.
+    loading: Vec<Loading>,
+    // Where to look after the importing file's own directory.
+    search_path: Vec<PathBuf>,
+    // The project's package and everything it depends on, by name.
+    packages: HashMap<String, Package>,
+    // How many syntax errors an imported file gets before the parser stops.
+    max_errors: usize,
+}
+
+struct Package {
+    root: PathBuf,
+    entry: PathBuf,
+    source_roots: Vec<PathBuf>,
+    // What files in this package can import by name.
+    dependencies: Vec<String>,
+}
+
+struct Loading {
+    path: PathBuf,
+    exports: Vec<String>,
+}
+
+impl Default for Modules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Modules {
+    // The search path starts out as whatever is in HORBA_PATH.
+    pub fn new() -> Modules {
+        let search_path = match env::var_os("HORBA_PATH") {
+            Some(x) => env::split_paths(&x).collect(),
+            None => Vec::new(),
+        };
+
+        Modules {
+            cache: HashMap::new(),
+            loading: Vec::new(),
+            search_path,
+            packages: HashMap::new(),
+            max_errors: parser::MAX_ERRORS,
+        }
+    }
+
+    pub fn add_search_path(&mut self, path: PathBuf) {
+        self.search_path.push(path);
+    }
+
+    // The package `name` if the importing file's own package depends on it. Files outside of
+    // every package, like a script run on its own, can't import any by name.
+    fn package(&self, name: &str) -> Option<&Package> {
+        let importer = &self.loading.last()?.path;
+        let importer = self
+            .packages
+            .values()
+            .filter(|x| importer.starts_with(&x.root))
+            // A package inside another's directory, like one in vendor/, is the closer one.
+            .max_by_key(|x| x.root.components().count())?;
+
+        match importer.dependencies.iter().any(|x| x == name) {
+            true => self.packages.get(name),
+            false => None,
+        }
+    }
+
+    // The file names in the cycle that importing `path` would make, starting and ending with it.
+    fn cycle(&self, path: &Path) -> Option<Vec<String>> {
+        let start = self.loading.iter().position(|x| x.path == path)?;
+        let mut cycle: Vec<String> = self.loading[start..]
+            .iter()
+            .map(|x| file_name(&x.path))
+            .collect();
+        cycle.push(file_name(path));
+        Some(cycle)
+    }
+}
+
+impl Interpreter {
+    // The file being run, so its imports are found relative to it and importing it back is a cycle.
+    pub fn set_script_path(&self, path: &Path) {
+        self.modules().borrow_mut().loading.push(Loading {
+            path: canonical(path),
+            exports: Vec::new(),
+        });
+    }
+
+    pub fn add_search_path(&self, path: PathBuf) {
+        self.modules().borrow_mut().add_search_path(path);
+    }
+
+    // Like --max-errors, for the files this imports as well as for the ones it's given.
+    pub fn set_max_errors(&self, max_errors: usize) {
+        self.modules().borrow_mut().max_errors = max_errors;
+    }
+
+    pub fn max_errors(&self) -> usize {
+        self.modules().borrow().max_errors
+    }
+
+    // Files in a package whose manifest depends on this one can then `import "name"` to run its
+    // entry file, or `import "name/path"` to look for the path in its source roots.
+    // The package being run is added too, for what it depends on.
+    pub fn add_package(&self, package: &manifest::Package) {
+        self.modules().borrow_mut().packages.insert(
+            package.name.clone(),
+            Package {
+                root: canonical(&package.root),
+                entry: package.entry.clone(),
+                source_roots: package.source_roots.clone(),
+                dependencies: package.dependencies.clone(),
+            },
+        );
+    }
+
+    // Exports from outside of any module, like in the REPL, don't go anywhere.
+    pub(super) fn export(&self, names: Vec<String>) {
+        if let Some(loading) = self.modules().borrow_mut().loading.last_mut() {
+            loading.exports.extend(names);
+        }
+    }
+
+    // Runs the module the first time it's imported, after that it comes from the cache.
+    pub(super) fn import(&self, import: &stmt::Import) -> Result<Rc<Module>, Unwind> {
+        let (path, name) = self.resolve_module(import)?;
+
+        if let Some(module) = self.modules().borrow().cache.get(&path) {
+            return Ok(module.clone());
+        }
+        if let Some(cycle) = self.modules().borrow().cycle(&path) {
+            return Err(RuntimeError::new(
+                &import.keyword,
+                format!("Import cycle: {}.", cycle.join(" -> ")),
+            )
+            .with_code("E0204")
+            .into());
+        }
+
+        let source = fs::read_to_string(&path).map_err(|e| {
+            RuntimeError::new(
+                &import.keyword,
+                format!("Couldn't read module '{}': {}.", import.path, e),
+            )
+            .with_code("E0204")
+        })?;
+
+        self.modules().borrow_mut().loading.push(Loading {
+            path: path.clone(),
+            exports: Vec::new(),
+        });
+        let environment = Rc::new(RefCell::new(Environment::new(Some(self.globals().clone()))));
+        let id = self.add_source(&path.display().to_string(), &source);
+        let ran = self.run_module(&source, id, environment.clone());
+        let loading = self.modules().borrow_mut().loading.pop();
+
+        // What went wrong points into the module's source, not the importer's, so it's
+        // passed on as notes.
+        if let Err(diagnostics) = ran {
+            let notes = diagnostics.iter().map(|x| {
+                format!(
+                    "{} line {}, column {}: {}",
+                    path.display(),
+                    x.span.line,
+                    x.span.column,
+                    x.message
+                )
+            });
+            let error = RuntimeError::new(
+                &import.keyword,
+                format!("Couldn't import '{}', it has errors.", import.path),
+            )
+            .with_code("E0204");
+            return Err(notes.fold(error, RuntimeError::with_note).into());
+        }
+
+        let module = Rc::new(Module {
+            name: name.unwrap_or_else(|| module_name(&path)),
+            environment,
+            exports: loading.map(|x| x.exports).unwrap_or_default(),
+        });
+        self.modules()
+            .borrow_mut()
+            .cache
+            .insert(path, module.clone());
+        Ok(module)
+    }
+
+    // Warnings in a module that imports fine aren't shown, only the importer's are.
+    fn run_module(
+        &self,
+        source: &str,
+        id: SourceId,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<(), Vec<Diagnostic>> {
+        let program = parser::parse_source(source, id, self.max_errors())?;
+        let scope = self.with_environment(environment);
+
+        for statement in program.statements {
+            if let Err(e) = scope.execute(&statement) {
+                return Err(vec![e.into_error().diagnostic()]);
+            }
+        }
+        Ok(())
+    }
+
+    // Packages come first, otherwise it looks next to the importing file, then along the search path.
+    // A package's entry file is named after the package rather than the file, which is usually
+    // just main or lib.
+    fn resolve_module(&self, import: &stmt::Import) -> Result<(PathBuf, Option<String>), Unwind> {
+        let modules = self.modules().borrow();
+
+        let (first, rest) = match import.path.split_once('/') {
+            Some((first, rest)) => (first, Some(rest)),
+            None => (import.path.as_str(), None),
+        };
+
+        let mut name = None;
+        let (mut file, searched) = match (modules.package(first), rest) {
+            (Some(package), None) => {
+                name = Some(first.to_string());
+                let directory = package.entry.parent().map(Path::to_path_buf);
+                let file = package.entry.file_name().map(PathBuf::from);
+                (
+                    file.unwrap_or_default(),
+                    vec![directory.unwrap_or_default()],
+                )
+            }
+            (Some(package), Some(rest)) => (PathBuf::from(rest), package.source_roots.clone()),
+            (None, _) => {
+                let importer = match modules.loading.last() {
+                    Some(x) => x.path.parent().map(Path::to_path_buf).unwrap_or_default(),
+                    None => env::current_dir().unwrap_or_default(),
+                };
+
+                let mut searched = vec![importer];
+                searched.extend(modules.search_path.iter().cloned());
+                (PathBuf::from(&import.path), searched)
+            }
+        };
+        if file.extension().is_none() {
+            file.set_extension("hb");
+        }
+
+        for directory in searched.iter() {
+            let candidate = directory.join(&file);
+            if candidate.is_file() {
+                return Ok((canonical(&candidate), name));
+            }
+        }
+
+        let searched: Vec<String> = searched.iter().map(|x| x.display().to_string()).collect();
+        Err(RuntimeError::new(
+            &import.keyword,
+            format!(
+                "Couldn't find module '{}', looked in: {}.",
+                import.path,
+                searched.join(", ")
+            ),
+        )
+        .with_code("E0204")
+        .into())
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn file_name(path: &Path) -> String {
+    match path.file_name() {
+        Some(x) => x.to_string_lossy().to_string(),
+        None => path.display().to_string(),
+    }
+}
+
+fn module_name(path: &Path) -> String {
+    match path.file_stem() {
+        Some(x) => x.to_string_lossy().to_string(),
+        None => path.display().to_string(),
+    }
+}
+
+// Modules are only ever equal to themselves.
+impl PartialEq for Module {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
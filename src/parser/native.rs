@@ -0,0 +1,55 @@
+use super::expr::Literal;
+use super::interpreter::Interpreter;
+use super::runtime_error::Unwind;
+use crate::scanner::token::Token;
+use std::fmt;
+use std::rc::Rc;
+
+// The token is the call's closing paren, for errors to point at.
+pub type NativeFn = dyn Fn(&Interpreter, Vec<Literal>, &Token) -> Result<Literal, Unwind>;
+
+// A function written in Rust. The interpreter checks the arity before calling it.
+pub struct NativeFunction {
+    pub name: String,
+    pub arity: usize,
+    // How many more arguments it can take after those, missing ones are passed as Null.
+    pub optional: usize,
+    pub function: Rc<NativeFn>,
+}
+
+impl NativeFunction {
+    pub fn new<F>(name: &str, arity: usize, function: F) -> NativeFunction
+    where
+        F: Fn(&Interpreter, Vec<Literal>, &Token) -> Result<Literal, Unwind> + 'static,
+    {
+        NativeFunction {
+            name: name.to_string(),
+            arity,
+            optional: 0,
+            function: Rc::new(function),
+        }
+    }
+
+    pub fn with_optional(mut self, optional: usize) -> NativeFunction {
+        self.optional = optional;
+        self
+    }
+}
+
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
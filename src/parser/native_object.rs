@@ -0,0 +1,82 @@
+use super::expr::Literal;
+use super::native::NativeFunction;
+use super::runtime_error::{RuntimeError, Unwind};
+use crate::scanner::token::Token;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+// A Rust value a host hands to scripts, like a database handle. Scripts use it with Dots:
+// `db.name` is get_property, `db.name = x` is set_property and `db.query(x)` is call_method.
+// Errors are plain messages, the interpreter points them at the script's Dot or call.
+pub trait NativeObject {
+    // What scripts see as its type, in errors and when it's printed.
+    fn type_name(&self) -> String;
+
+    // None if there's no such property, which is an error unless there's a method by that name.
+    fn get_property(&self, _name: &str) -> Option<Literal> {
+        None
+    }
+
+    fn set_property(&mut self, name: &str, _value: Literal) -> Result<(), String> {
+        Err(format!("Can't set '{}' on {}.", name, self.type_name()))
+    }
+
+    // How many arguments the method takes, or None if there's no method by that name.
+    fn method_arity(&self, _name: &str) -> Option<usize> {
+        None
+    }
+
+    // Only called for names method_arity knows, with that many arguments.
+    fn call_method(&mut self, name: &str, _arguments: Vec<Literal>) -> Result<Literal, String> {
+        Err(format!("{} has no method '{}'.", self.type_name(), name))
+    }
+}
+
+pub fn object<T: NativeObject + 'static>(value: T) -> Literal {
+    Literal::Object(Rc::new(RefCell::new(value)))
+}
+
+// A property, or a method with the object already filled in.
+pub fn get(object: &Rc<RefCell<dyn NativeObject>>, name: &Token) -> Option<Literal> {
+    if let Some(x) = object.borrow().get_property(&name.lexeme) {
+        return Some(x);
+    }
+
+    let arity = object.borrow().method_arity(&name.lexeme)?;
+    let object = object.clone();
+    let method = name.lexeme.clone();
+    let native = NativeFunction::new(&name.lexeme, arity, move |_, arguments, paren| {
+        let result = object.borrow_mut().call_method(&method, arguments);
+        result.map_err(|message| RuntimeError::new(paren, message).into())
+    });
+    Some(Literal::Native(Rc::new(native)))
+}
+
+pub fn set(
+    object: &Rc<RefCell<dyn NativeObject>>,
+    name: &Token,
+    value: Literal,
+) -> Result<(), Unwind> {
+    let result = object.borrow_mut().set_property(&name.lexeme, value);
+    result.map_err(|message| RuntimeError::new(name, message).into())
+}
+
+// Objects are only ever equal to themselves.
+impl PartialEq for dyn NativeObject {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(self, other)
+    }
+}
+
+impl fmt::Debug for dyn NativeObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for dyn NativeObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{}>", self.type_name())
+    }
+}
@@ -0,0 +1,118 @@
+use super::expr::Literal;
+use crate::scanner::token::Token;
+use std::fmt;
+
+// Patterns are used on the left hand side of match arms and let declarations.
+#[derive(Debug)]
+pub enum Pattern {
+    Literal(Literal),
+    Range(Range),
+    Binding(Token),
+    Wildcard,
+    Struct(StructPattern),
+    Variant(VariantPattern),
+    Array(ArrayPattern),
+}
+
+impl Pattern {
+    // A pattern is irrefutable if it matches any value whatsoever.
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, Pattern::Binding(_) | Pattern::Wildcard)
+    }
+
+    // Every name the pattern binds when it matches.
+    pub fn bindings<'a>(&'a self, names: &mut Vec<&'a Token>) {
+        match self {
+            Pattern::Binding(x) => names.push(x),
+            Pattern::Struct(x) => x.fields.iter().for_each(|(_, x)| x.bindings(names)),
+            Pattern::Variant(x) => x.fields.iter().flatten().for_each(|x| x.bindings(names)),
+            Pattern::Array(x) => {
+                x.elements.iter().for_each(|x| x.bindings(names));
+                if let Some(rest) = &x.rest {
+                    rest.bindings(names);
+                }
+            }
+            Pattern::Literal(_) | Pattern::Range(_) | Pattern::Wildcard => (),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Range {
+    pub start: Literal,
+    pub end: Literal,
+    pub inclusive: bool,
+}
+
+// Without a name, `{ x, y }` matches anything that has those fields.
+#[derive(Debug)]
+pub struct StructPattern {
+    pub name: Option<Token>,
+    pub fields: Vec<(Token, Pattern)>,
+}
+
+// `Shape.Circle(r)`, or just `Circle(r)`. Leaving the parentheses off matches the variant whatever
+// its payload is, but a bare `Empty` is a binding, so variants without a payload need the enum name.
+#[derive(Debug)]
+pub struct VariantPattern {
+    pub enum_name: Option<Token>,
+    pub name: Token,
+    pub fields: Option<Vec<Pattern>>,
+}
+
+// `[a, b, ...rest]` binds whatever is left over to `rest` as a new array.
+// Without a rest pattern the array has to be exactly as long as the pattern.
+#[derive(Debug)]
+pub struct ArrayPattern {
+    pub elements: Vec<Pattern>,
+    pub rest: Option<Box<Pattern>>,
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Pattern::*;
+
+        match &self {
+            Literal(x) => write!(f, "{}", x),
+            Range(x) => write!(
+                f,
+                "{}{}{}",
+                x.start,
+                if x.inclusive { "..=" } else { ".." },
+                x.end
+            ),
+            Binding(x) => write!(f, "{}", x.lexeme),
+            Wildcard => write!(f, "_"),
+            Struct(x) => {
+                let fields: Vec<String> = x
+                    .fields
+                    .iter()
+                    .map(|(name, pattern)| format!("{}: {}", name.lexeme, pattern))
+                    .collect();
+                match &x.name {
+                    Some(name) => write!(f, "{} {{ {} }}", name.lexeme, fields.join(", ")),
+                    None => write!(f, "{{ {} }}", fields.join(", ")),
+                }
+            }
+            Variant(x) => {
+                if let Some(enum_name) = &x.enum_name {
+                    write!(f, "{}.", enum_name.lexeme)?;
+                }
+                write!(f, "{}", x.name.lexeme)?;
+
+                if let Some(fields) = &x.fields {
+                    let fields: Vec<String> = fields.iter().map(|x| x.to_string()).collect();
+                    write!(f, "({})", fields.join(", "))?;
+                }
+                Ok(())
+            }
+            Array(x) => {
+                let mut elements: Vec<String> = x.elements.iter().map(|x| x.to_string()).collect();
+                if let Some(rest) = &x.rest {
+                    elements.push(format!("...{}", rest));
+                }
+                write!(f, "[{}]", elements.join(", "))
+            }
+        }
+    }
+}
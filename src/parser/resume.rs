@@ -0,0 +1,363 @@
+use super::expr::{Await, Expr, Literal};
+use super::generator::Iteration;
+use super::interpreter::Interpreter;
+use super::runtime_error::Unwind;
+use super::stmt::{self, Stmt};
+
+// A tree walker keeps its place in the Rust call stack, which can't be paused and picked up later.
+// So generators and tasks keep their place as data instead: one Frame per statement they are part
+// way through, outermost first. Resuming walks back down the AST following the frames, skipping
+// what already ran.
+
+pub enum Frame {
+    // The statement being run in a block, and the block's scope.
+    Block { index: usize, scope: Interpreter },
+    If { then_branch: bool },
+    // While and For bodies have a Block frame above this one while they are running.
+    While,
+    For(Iteration),
+    Try(TryFrame),
+    // What the awaited task finished with, filled in just before the task is resumed.
+    Await(Option<Result<Literal, Unwind>>),
+}
+
+pub struct TryFrame {
+    phase: TryPhase,
+    // What catch binds, between the try block unwinding and the catch block starting.
+    caught: Option<Literal>,
+    // Whatever was on its way out when finally started, to carry on with once it's done.
+    pending: Option<Unwind>,
+}
+
+#[derive(Clone, Copy)]
+enum TryPhase {
+    Try,
+    Catch,
+    Finally,
+}
+
+// Suspend carries the yielded value for generators, and the awaited task for async functions.
+pub enum Flow {
+    Done,
+    Suspend(Literal),
+}
+
+impl Interpreter {
+    // Picks up from frames[level] if it's there, otherwise starts the block from the top.
+    // On anything but a suspend, the block's frames are gone by the time this returns.
+    pub(super) fn resume_block(
+        &self,
+        statements: &[Stmt],
+        frames: &mut Vec<Frame>,
+        level: usize,
+        bindings: Vec<(String, Literal)>,
+    ) -> Result<Flow, Unwind> {
+        if frames.len() == level {
+            frames.push(Frame::Block {
+                index: 0,
+                scope: self.with_bindings(bindings),
+            });
+        }
+
+        loop {
+            let (index, scope) = match &frames[level] {
+                Frame::Block { index, scope } => (*index, scope.clone()),
+                _ => unreachable!("blocks always resume from a Block frame"),
+            };
+
+            let statement = match statements.get(index) {
+                Some(x) => x,
+                None => {
+                    frames.truncate(level);
+                    return Ok(Flow::Done);
+                }
+            };
+
+            match scope.resume_statement(statement, frames, level + 1) {
+                Ok(Flow::Suspend(x)) => {
+                    // A yield is over as soon as it suspends, anything else carries on where it was.
+                    if matches!(statement, Stmt::Yield(_)) {
+                        Interpreter::advance(frames, level);
+                    }
+                    return Ok(Flow::Suspend(x));
+                }
+                Ok(Flow::Done) => Interpreter::advance(frames, level),
+                Err(x) => {
+                    frames.truncate(level);
+                    return Err(x);
+                }
+            }
+        }
+    }
+
+    fn advance(frames: &mut [Frame], level: usize) {
+        if let Frame::Block { index, .. } = &mut frames[level] {
+            *index += 1;
+        }
+    }
+
+    // Statements that can't contain a yield are run as normal, the rest have resumable versions here.
+    fn resume_statement(
+        &self,
+        statement: &Stmt,
+        frames: &mut Vec<Frame>,
+        level: usize,
+    ) -> Result<Flow, Unwind> {
+        match statement {
+            Stmt::Yield(x) => Ok(Flow::Suspend(match &x.value {
+                Some(value) => self.evaluate(value)?,
+                None => Literal::Null,
+            })),
+            Stmt::Block(x) => self.resume_block(&x.statements, frames, level, Vec::new()),
+            Stmt::If(x) => self.resume_if(x, frames, level),
+            Stmt::While(x) => self.resume_while(x, frames, level),
+            Stmt::For(x) => self.resume_for(x, frames, level),
+            Stmt::Try(x) => self.resume_try(x, frames, level),
+            Stmt::Expression(stmt::Expression {
+                expression: Expr::Await(x),
+                ..
+            }) => self.resume_await(x, frames, level, |_| Ok(())),
+            Stmt::Let(x) => match x.initialisers.first() {
+                Some(Expr::Await(await_expr)) => {
+                    self.resume_await(await_expr, frames, level, |value| {
+                        self.bind_let(x, vec![value])
+                    })
+                }
+                _ => self.execute(statement).map(|_| Flow::Done),
+            },
+            Stmt::Print(stmt::Print {
+                expression: Expr::Await(x),
+                ..
+            }) => self.resume_await(x, frames, level, |value| {
+                println!("{}", value);
+                Ok(())
+            }),
+            Stmt::Return(stmt::Return {
+                value: Some(Expr::Await(x)),
+                ..
+            }) => self.resume_await(x, frames, level, |value| Err(Unwind::Return(value))),
+            x => {
+                self.execute(x)?;
+                Ok(Flow::Done)
+            }
+        }
+    }
+
+    fn resume_if(
+        &self,
+        if_stmt: &stmt::If,
+        frames: &mut Vec<Frame>,
+        level: usize,
+    ) -> Result<Flow, Unwind> {
+        if frames.len() == level {
+            let condition = self.evaluate(&if_stmt.condition)?;
+            frames.push(Frame::If {
+                then_branch: Literal::is_truthy(&condition),
+            });
+        }
+
+        let branch = match frames[level] {
+            Frame::If { then_branch: true } => Some(&if_stmt.then_branch),
+            _ => if_stmt.else_branch.as_ref(),
+        };
+
+        let flow = match branch {
+            Some(x) => self.resume_block(x, frames, level + 1, Vec::new()),
+            None => Ok(Flow::Done),
+        };
+        Interpreter::finish(flow, frames, level)
+    }
+
+    fn resume_while(
+        &self,
+        while_stmt: &stmt::While,
+        frames: &mut Vec<Frame>,
+        level: usize,
+    ) -> Result<Flow, Unwind> {
+        if frames.len() == level {
+            frames.push(Frame::While);
+        }
+
+        loop {
+            // Only check the condition between runs of the body, not when resuming part way through it.
+            if frames.len() == level + 1 {
+                let condition = self.evaluate(&while_stmt.condition);
+                match condition {
+                    Ok(x) if Literal::is_truthy(&x) => (),
+                    x => return Interpreter::finish(x.map(|_| Flow::Done), frames, level),
+                }
+            }
+
+            match self.resume_block(&while_stmt.body, frames, level + 1, Vec::new()) {
+                Ok(Flow::Done) => continue,
+                x => return Interpreter::finish(x, frames, level),
+            }
+        }
+    }
+
+    fn resume_for(
+        &self,
+        for_stmt: &stmt::For,
+        frames: &mut Vec<Frame>,
+        level: usize,
+    ) -> Result<Flow, Unwind> {
+        if frames.len() == level {
+            let iterable = self.evaluate(&for_stmt.iterable)?;
+            frames.push(Frame::For(Iteration::new(iterable, &for_stmt.keyword)?));
+        }
+
+        loop {
+            let mut bindings = Vec::new();
+
+            if frames.len() == level + 1 {
+                let next = match &mut frames[level] {
+                    Frame::For(iteration) => iteration.next(self, &for_stmt.keyword),
+                    _ => unreachable!("for loops always resume from a For frame"),
+                };
+
+                match next {
+                    Ok(Some(x)) => {
+                        if let Err(x) = self.bind_for(for_stmt, &x, &mut bindings) {
+                            return Interpreter::finish(Err(x), frames, level);
+                        }
+                    }
+                    x => return Interpreter::finish(x.map(|_| Flow::Done), frames, level),
+                }
+            }
+
+            match self.resume_block(&for_stmt.body, frames, level + 1, bindings) {
+                Ok(Flow::Done) => continue,
+                x => return Interpreter::finish(x, frames, level),
+            }
+        }
+    }
+
+    fn resume_try(
+        &self,
+        try_stmt: &stmt::Try,
+        frames: &mut Vec<Frame>,
+        level: usize,
+    ) -> Result<Flow, Unwind> {
+        if frames.len() == level {
+            frames.push(Frame::Try(TryFrame {
+                phase: TryPhase::Try,
+                caught: None,
+                pending: None,
+            }));
+        }
+
+        loop {
+            let phase = match &frames[level] {
+                Frame::Try(x) => x.phase,
+                _ => unreachable!("try statements always resume from a Try frame"),
+            };
+
+            let flow = match phase {
+                TryPhase::Try => {
+                    self.resume_block(&try_stmt.try_block, frames, level + 1, Vec::new())
+                }
+                TryPhase::Catch => {
+                    let caught = match &mut frames[level] {
+                        Frame::Try(x) => x.caught.take(),
+                        _ => None,
+                    };
+                    let bindings = match (&try_stmt.catch_name, caught) {
+                        (Some(name), Some(caught)) => vec![(name.lexeme.clone(), caught)],
+                        _ => Vec::new(),
+                    };
+                    // There's always a catch block if we got to this phase.
+                    let catch_block = try_stmt.catch_block.as_ref().unwrap();
+                    self.resume_block(catch_block, frames, level + 1, bindings)
+                }
+                TryPhase::Finally => {
+                    let flow = match &try_stmt.finally_block {
+                        Some(x) => self.resume_block(x, frames, level + 1, Vec::new()),
+                        None => Ok(Flow::Done),
+                    };
+                    let pending = match &mut frames[level] {
+                        Frame::Try(x) => x.pending.take(),
+                        _ => None,
+                    };
+
+                    return match (flow, pending) {
+                        (Ok(Flow::Suspend(x)), pending) => {
+                            if let Frame::Try(frame) = &mut frames[level] {
+                                frame.pending = pending;
+                            }
+                            Ok(Flow::Suspend(x))
+                        }
+                        (Ok(Flow::Done), Some(pending)) => {
+                            Interpreter::finish(Err(pending), frames, level)
+                        }
+                        (flow, _) => Interpreter::finish(flow, frames, level),
+                    };
+                }
+            };
+
+            let frame = match &mut frames[level] {
+                Frame::Try(x) => x,
+                _ => unreachable!("try statements always resume from a Try frame"),
+            };
+
+            match (phase, flow) {
+                (_, Ok(Flow::Suspend(x))) => return Ok(Flow::Suspend(x)),
+                (_, Ok(Flow::Done)) => frame.phase = TryPhase::Finally,
+                (TryPhase::Try, Err(unwind)) if try_stmt.catch_block.is_some() => {
+                    match unwind.caught_value(self.error_type()) {
+                        Ok(caught) => {
+                            frame.caught = Some(caught);
+                            frame.phase = TryPhase::Catch;
+                        }
+                        Err(unwind) => {
+                            frame.pending = Some(unwind);
+                            frame.phase = TryPhase::Finally;
+                        }
+                    }
+                }
+                (_, Err(unwind)) => {
+                    frame.pending = Some(unwind);
+                    frame.phase = TryPhase::Finally;
+                }
+            }
+        }
+    }
+
+    // Suspends with the task to wait for, then once it's finished and this is resumed,
+    // hands what it finished with to `then`, which does the rest of the statement.
+    fn resume_await<F>(
+        &self,
+        await_expr: &Await,
+        frames: &mut Vec<Frame>,
+        level: usize,
+        then: F,
+    ) -> Result<Flow, Unwind>
+    where
+        F: FnOnce(Literal) -> Result<(), Unwind>,
+    {
+        if frames.len() == level {
+            let task = self.awaited_task(await_expr)?;
+            frames.push(Frame::Await(None));
+            return Ok(Flow::Suspend(Literal::Task(task)));
+        }
+
+        match frames.pop() {
+            Some(Frame::Await(Some(result))) => {
+                then(result?)?;
+                Ok(Flow::Done)
+            }
+            _ => unreachable!("tasks are only resumed once what they're waiting on has finished"),
+        }
+    }
+
+    // Drops the statement's frame unless it's suspending, in which case it's needed to resume.
+    fn finish(
+        flow: Result<Flow, Unwind>,
+        frames: &mut Vec<Frame>,
+        level: usize,
+    ) -> Result<Flow, Unwind> {
+        if !matches!(flow, Ok(Flow::Suspend(_))) {
+            frames.truncate(level);
+        }
+        flow
+    }
+}
@@ -1,11 +1,89 @@
-use crate::error;
-use crate::scanner::token::Token;
+use super::expr::Literal;
+use super::instance::{Instance, StructType};
+use crate::error::{self, Diagnostic, Label, LogLevel};
+use crate::scanner::token::{Span, Token};
+use indexmap::IndexMap;
+use std::cell::RefCell;
 use std::fmt;
+use std::rc::Rc;
 
+#[derive(Clone)]
 pub struct RuntimeError {
     pub token: Token,
     pub log_level: error::LogLevel,
+    // Which kind of error it is, see the table in error.rs.
+    pub code: &'static str,
     pub message: String,
+    // Anything else worth saying, shown under the error.
+    pub notes: Vec<String>,
+    // Other parts of the script that explain it, like what each side of a `+` was.
+    pub labels: Vec<Label>,
+}
+
+impl RuntimeError {
+    // An error at the token, with the catch-all code until with_code says what kind it is.
+    pub fn new(token: &Token, message: impl Into<String>) -> RuntimeError {
+        RuntimeError {
+            token: token.clone(),
+            log_level: LogLevel::Error,
+            code: "E0200",
+            message: message.into(),
+            notes: Vec::new(),
+            labels: Vec::new(),
+        }
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> RuntimeError {
+        self.code = code;
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> RuntimeError {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> RuntimeError {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    // The struct every caught RuntimeError turns into, so scripts can do `e is Error`.
+    // The interpreter makes one and keeps it, `is` goes by which struct it is, not its name.
+    pub fn error_type() -> StructType {
+        StructType {
+            name: "Error".to_string(),
+            fields: vec![
+                "message".to_string(),
+                "line".to_string(),
+                "column".to_string(),
+            ],
+        }
+    }
+
+    pub fn to_instance(&self, error_type: &Rc<StructType>) -> Literal {
+        let mut fields = IndexMap::new();
+        fields.insert("message".to_string(), Literal::String(self.message.clone()));
+        fields.insert("line".to_string(), Literal::Number(self.token.line as f64));
+        fields.insert(
+            "column".to_string(),
+            Literal::Number(self.token.column as f64),
+        );
+
+        Literal::Instance(Rc::new(RefCell::new(Instance {
+            structure: error_type.clone(),
+            fields,
+        })))
+    }
+}
+
+impl fmt::Debug for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
 }
 
 impl fmt::Display for RuntimeError {
@@ -15,14 +93,59 @@ impl fmt::Display for RuntimeError {
 }
 
 impl error::Error for RuntimeError {
-    fn report(&self, source: &str) {
-        error::report(
-            self.token.line,
-            self.token.column,
-            self.log_level,
-            "",
-            &self.message,
-            source,
-        )
+    fn diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            severity: self.log_level,
+            code: self.code,
+            message: self.message.clone(),
+            span: self.token.span(),
+            labels: self.labels.clone(),
+            notes: self.notes.clone(),
+            help: Vec::new(),
+        }
+    }
+}
+
+// Anything that stops the interpreter part way through a statement.
+// Only Error comes from the interpreter itself, the rest are the script asking for it.
+// Error and Throw are boxed, every call returns an Unwind and most never make one.
+#[derive(Clone)]
+pub enum Unwind {
+    Error(Box<RuntimeError>),
+    Throw(Literal, Box<Token>),
+    // Caught by the function call, the parser makes sure there always is one.
+    Return(Literal),
+}
+
+impl Unwind {
+    // What a `catch` binds when it catches this. Returns aren't caught, so they're handed back.
+    pub fn caught_value(self, error_type: &Rc<StructType>) -> Result<Literal, Unwind> {
+        match self {
+            Unwind::Error(x) => Ok(x.to_instance(error_type)),
+            Unwind::Throw(x, _) => Ok(x),
+            x => Err(x),
+        }
+    }
+
+    // Turns anything that reached the top level without being caught into something to report.
+    pub fn into_error(self) -> RuntimeError {
+        *self.into_boxed_error()
+    }
+
+    // The same, left in its box for passing on.
+    pub fn into_boxed_error(self) -> Box<RuntimeError> {
+        match self {
+            Unwind::Error(x) => x,
+            Unwind::Throw(x, token) => Box::new(
+                RuntimeError::new(&token, format!("Uncaught exception: {}", x)).with_code("E0209"),
+            ),
+            Unwind::Return(_) => unreachable!("the parser rejects return outside of functions"),
+        }
+    }
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(error: RuntimeError) -> Self {
+        Unwind::Error(Box::new(error))
     }
 }
@@ -0,0 +1,72 @@
+use super::io::io_error;
+use super::{function, string};
+use crate::parser::expr::Literal;
+use crate::parser::module::Module;
+use std::cell::RefCell;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
+
+pub fn module() -> Module {
+    let members = vec![
+        function("read_file", 1, |_, arguments, paren| {
+            let path = string(&arguments[0], paren)?;
+            fs::read_to_string(&path)
+                .map(Literal::String)
+                .map_err(|e| io_error(&format!("Couldn't read '{}'", path), e, paren))
+        }),
+        // Makes the file if it isn't there, and replaces what's in it if it is.
+        function("write_file", 2, |_, arguments, paren| {
+            let path = string(&arguments[0], paren)?;
+            let contents = string(&arguments[1], paren)?;
+            fs::write(&path, contents)
+                .map_err(|e| io_error(&format!("Couldn't write '{}'", path), e, paren))?;
+            Ok(Literal::Null)
+        }),
+        function("append_file", 2, |_, arguments, paren| {
+            let path = string(&arguments[0], paren)?;
+            let contents = string(&arguments[1], paren)?;
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(contents.as_bytes()))
+                .map_err(|e| io_error(&format!("Couldn't append to '{}'", path), e, paren))?;
+            Ok(Literal::Null)
+        }),
+        function("exists", 1, |_, arguments, paren| {
+            match Path::new(&string(&arguments[0], paren)?).exists() {
+                true => Ok(Literal::True),
+                false => Ok(Literal::False),
+            }
+        }),
+        // The names of everything in the directory, sorted so it's the same on every OS.
+        function("list_dir", 1, |_, arguments, paren| {
+            let path = string(&arguments[0], paren)?;
+            let error = |e| io_error(&format!("Couldn't list '{}'", path), e, paren);
+
+            let mut names = Vec::new();
+            for entry in fs::read_dir(&path).map_err(error)? {
+                let name = entry.map_err(error)?.file_name();
+                names.push(name.to_string_lossy().to_string());
+            }
+            names.sort();
+
+            let names = names.into_iter().map(Literal::String).collect();
+            Ok(Literal::Array(Rc::new(RefCell::new(names))))
+        }),
+        // Directories have to be empty, there's no removing a whole tree by accident.
+        function("remove", 1, |_, arguments, paren| {
+            let path = string(&arguments[0], paren)?;
+            let removed = match Path::new(&path).is_dir() {
+                true => fs::remove_dir(&path),
+                false => fs::remove_file(&path),
+            };
+            removed.map_err(|e| io_error(&format!("Couldn't remove '{}'", path), e, paren))?;
+            Ok(Literal::Null)
+        }),
+    ];
+
+    Module::native("fs", members)
+}
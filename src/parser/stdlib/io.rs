@@ -0,0 +1,42 @@
+use super::function;
+use crate::parser::expr::Literal;
+use crate::parser::module::Module;
+use crate::parser::runtime_error::{RuntimeError, Unwind};
+use crate::scanner::token::Token;
+use std::io::{self, BufRead, Write};
+
+pub fn module() -> Module {
+    let members = vec![
+        // The next line from stdin without its newline, or null once there's nothing left.
+        function("read_line", 0, |_, _, paren| {
+            let mut line = String::new();
+            match io::stdin().lock().read_line(&mut line) {
+                Ok(0) => Ok(Literal::Null),
+                Ok(_) => {
+                    let trimmed = line.trim_end_matches(['\n', '\r']);
+                    Ok(Literal::String(trimmed.to_string()))
+                }
+                Err(e) => Err(io_error("Couldn't read from stdin", e, paren)),
+            }
+        }),
+        // Like print but without the newline on the end.
+        function("write", 1, |_, arguments, paren| {
+            let text = arguments[0].to_string();
+            let mut stdout = io::stdout().lock();
+            stdout
+                .write_all(text.as_bytes())
+                .and_then(|_| stdout.flush())
+                .map_err(|e| io_error("Couldn't write to stdout", e, paren))?;
+            Ok(Literal::Null)
+        }),
+    ];
+
+    Module::native("io", members)
+}
+
+// The OS's own message goes on the end, so the script can tell what went wrong.
+pub(super) fn io_error(what: &str, error: io::Error, paren: &Token) -> Unwind {
+    RuntimeError::new(paren, format!("{}: {}.", what, error))
+        .with_code("E0210")
+        .into()
+}
@@ -0,0 +1,176 @@
+use super::{function, function_with_optional, object, string};
+use crate::parser::expr::Literal;
+use crate::parser::module::Module;
+use crate::parser::runtime_error::{RuntimeError, Unwind};
+use crate::scanner::token::Token;
+use serde_json::Value;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub fn module() -> Module {
+    let members = vec![
+        function("parse", 1, |_, arguments, paren| {
+            let text = string(&arguments[0], paren)?;
+            let value: Value = serde_json::from_str(&text).map_err(|e| {
+                RuntimeError::new(paren, format!("Invalid JSON: {}.", e)).with_code("E0211")
+            })?;
+            Ok(from_json(value))
+        }),
+        // The indent is a number of spaces or the string to indent with, without one it's all
+        // on one line.
+        function_with_optional("stringify", 1, 1, |_, arguments, paren| {
+            let indent = match &arguments[1] {
+                Literal::Null => None,
+                Literal::Number(x) if x.fract() == 0.0 && *x >= 0.0 => {
+                    Some(" ".repeat(*x as usize))
+                }
+                Literal::String(x) => Some(x.clone()),
+                x => {
+                    return Err(RuntimeError::new(
+                        paren,
+                        format!(
+                            "The indent should be a whole Number or a String, got {}.",
+                            Literal::type_name(x)
+                        ),
+                    )
+                    .with_code("E0202")
+                    .into())
+                }
+            };
+
+            let mut writer = Writer {
+                indent,
+                output: String::new(),
+                parents: Vec::new(),
+                paren,
+            };
+            writer.write(&arguments[0], 0)?;
+            Ok(Literal::String(writer.output))
+        }),
+    ];
+
+    Module::native("json", members)
+}
+
+// Objects keep their keys in the order they were written, which is how they're printed and
+// stringified. They're equal to any other Object with the same keys and values, in any order.
+fn from_json(value: Value) -> Literal {
+    match value {
+        Value::Null => Literal::Null,
+        Value::Bool(true) => Literal::True,
+        Value::Bool(false) => Literal::False,
+        Value::Number(x) => Literal::Number(x.as_f64().unwrap_or(f64::NAN)),
+        Value::String(x) => Literal::String(x),
+        Value::Array(x) => {
+            let elements = x.into_iter().map(from_json).collect();
+            Literal::Array(Rc::new(RefCell::new(elements)))
+        }
+        Value::Object(x) => object(x.into_iter().map(|(k, v)| (k, from_json(v))).collect()),
+    }
+}
+
+struct Writer<'a> {
+    indent: Option<String>,
+    output: String,
+    // The arrays and instances being written, anything in here showing up again is a cycle.
+    parents: Vec<*const ()>,
+    paren: &'a Token,
+}
+
+impl Writer<'_> {
+    fn write(&mut self, value: &Literal, depth: usize) -> Result<(), Unwind> {
+        match value {
+            Literal::Null => self.output.push_str("null"),
+            Literal::True => self.output.push_str("true"),
+            Literal::False => self.output.push_str("false"),
+            Literal::Number(x) if x.is_finite() => self.output.push_str(&x.to_string()),
+            Literal::Number(x) => {
+                return Err(RuntimeError::new(
+                    self.paren,
+                    format!("JSON has no way to write {}.", x),
+                )
+                .with_code("E0211")
+                .into())
+            }
+            Literal::String(x) => self.write_string(x),
+            Literal::Array(array) => {
+                self.enter(Rc::as_ptr(array) as *const ())?;
+                let elements = array.borrow();
+                self.output.push('[');
+                for (i, element) in elements.iter().enumerate() {
+                    self.item(i, depth + 1);
+                    self.write(element, depth + 1)?;
+                }
+                self.close(']', elements.is_empty(), depth);
+                self.parents.pop();
+            }
+            // Every struct is written as an object, fields in the order they were declared, or
+            // for an Object, the order its keys came in.
+            Literal::Instance(instance) => {
+                self.enter(Rc::as_ptr(instance) as *const ())?;
+                let instance = instance.borrow();
+                let separator = match self.indent {
+                    Some(_) => ": ",
+                    None => ":",
+                };
+                self.output.push('{');
+                for (i, (name, value)) in instance.fields.iter().enumerate() {
+                    self.item(i, depth + 1);
+                    self.write_string(name);
+                    self.output.push_str(separator);
+                    self.write(value, depth + 1)?;
+                }
+                self.close('}', instance.fields.is_empty(), depth);
+                self.parents.pop();
+            }
+            x => {
+                return Err(RuntimeError::new(
+                    self.paren,
+                    format!("Can't turn a {} into JSON.", Literal::type_name(x)),
+                )
+                .with_code("E0202")
+                .into())
+            }
+        }
+
+        Ok(())
+    }
+
+    fn enter(&mut self, pointer: *const ()) -> Result<(), Unwind> {
+        if self.parents.contains(&pointer) {
+            return Err(RuntimeError::new(
+                self.paren,
+                "Can't turn a structure that contains itself into JSON.",
+            )
+            .with_code("E0211")
+            .into());
+        }
+        self.parents.push(pointer);
+        Ok(())
+    }
+
+    // What goes before the i'th element of an array or object, at its depth.
+    fn item(&mut self, i: usize, depth: usize) {
+        if i > 0 {
+            self.output.push(',');
+        }
+        if let Some(indent) = &self.indent {
+            self.output.push('\n');
+            self.output.push_str(&indent.repeat(depth));
+        }
+    }
+
+    // Empty ones stay on one line even when indenting.
+    fn close(&mut self, close: char, empty: bool, depth: usize) {
+        if let Some(indent) = self.indent.as_ref().filter(|_| !empty) {
+            self.output.push('\n');
+            self.output.push_str(&indent.repeat(depth));
+        }
+        self.output.push(close);
+    }
+
+    fn write_string(&mut self, text: &str) {
+        self.output
+            .push_str(&Value::String(text.to_string()).to_string());
+    }
+}
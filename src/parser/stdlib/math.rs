@@ -0,0 +1,65 @@
+use super::{function, number};
+use crate::parser::expr::Literal;
+use crate::parser::module::Module;
+use std::f64::consts;
+
+type Unary = fn(f64) -> f64;
+type Binary = fn(f64, f64) -> f64;
+
+pub fn module() -> Module {
+    let mut members = vec![
+        ("PI".to_string(), Literal::Number(consts::PI)),
+        ("E".to_string(), Literal::Number(consts::E)),
+        ("INF".to_string(), Literal::Number(f64::INFINITY)),
+        ("NAN".to_string(), Literal::Number(f64::NAN)),
+    ];
+
+    let unary: [(&str, Unary); 15] = [
+        ("sqrt", f64::sqrt),
+        ("abs", f64::abs),
+        ("floor", f64::floor),
+        ("ceil", f64::ceil),
+        ("round", f64::round),
+        ("sin", f64::sin),
+        ("cos", f64::cos),
+        ("tan", f64::tan),
+        ("asin", f64::asin),
+        ("acos", f64::acos),
+        ("atan", f64::atan),
+        // Natural log, see log_base for any other base.
+        ("log", f64::ln),
+        ("log10", f64::log10),
+        ("log2", f64::log2),
+        ("exp", f64::exp),
+    ];
+    for (name, f) in unary {
+        members.push(function(name, 1, move |_, arguments, paren| {
+            Ok(Literal::Number(f(number(&arguments[0], paren)?)))
+        }));
+    }
+
+    let binary: [(&str, Binary); 5] = [
+        ("pow", f64::powf),
+        ("atan2", f64::atan2),
+        ("min", f64::min),
+        ("max", f64::max),
+        ("log_base", f64::log),
+    ];
+    for (name, f) in binary {
+        members.push(function(name, 2, move |_, arguments, paren| {
+            let x = number(&arguments[0], paren)?;
+            let y = number(&arguments[1], paren)?;
+            Ok(Literal::Number(f(x, y)))
+        }));
+    }
+
+    // NaN isn't equal to anything, itself included, so this is the only way to check for it.
+    members.push(function("is_nan", 1, |_, arguments, paren| {
+        match number(&arguments[0], paren)?.is_nan() {
+            true => Ok(Literal::True),
+            false => Ok(Literal::False),
+        }
+    }));
+
+    Module::native("math", members)
+}
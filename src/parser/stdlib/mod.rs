@@ -0,0 +1,99 @@
+use super::environment::Environment;
+use super::expr::Literal;
+use super::instance::{Instance, StructType};
+use super::interpreter::{Interpreter, LiteralWithToken};
+use super::module::Module;
+use super::native::NativeFunction;
+use super::runtime_error::Unwind;
+use crate::scanner::token::Token;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub mod fs;
+pub mod io;
+pub mod json;
+pub mod math;
+pub mod os;
+pub mod process;
+pub mod random;
+pub mod regex;
+pub mod time;
+
+// The native modules, each one is a global named after it.
+pub fn define(globals: &mut Environment) {
+    define_module(globals, fs::module());
+    define_module(globals, io::module());
+    define_module(globals, json::module());
+    define_module(globals, math::module());
+    define_module(globals, os::module(Vec::new()));
+    define_module(globals, process::module());
+    define_module(globals, random::module());
+    define_module(globals, regex::module());
+    define_module(globals, time::module());
+}
+
+fn define_module(globals: &mut Environment, module: Module) {
+    globals.define(module.name.clone(), Literal::Module(Rc::new(module)));
+}
+
+// A module member that's a native function.
+fn function<F>(name: &str, arity: usize, function: F) -> (String, Literal)
+where
+    F: Fn(&Interpreter, Vec<Literal>, &Token) -> Result<Literal, Unwind> + 'static,
+{
+    function_with_optional(name, arity, 0, function)
+}
+
+// Like `function`, but it can also take up to `optional` more arguments, which are Null if missing.
+fn function_with_optional<F>(
+    name: &str,
+    arity: usize,
+    optional: usize,
+    function: F,
+) -> (String, Literal)
+where
+    F: Fn(&Interpreter, Vec<Literal>, &Token) -> Result<Literal, Unwind> + 'static,
+{
+    let native = NativeFunction::new(name, arity, function).with_optional(optional);
+    (name.to_string(), Literal::Native(Rc::new(native)))
+}
+
+// Type errors in natives point at the call's closing paren.
+fn number(value: &Literal, paren: &Token) -> Result<f64, Unwind> {
+    Ok(f64::try_from(LiteralWithToken(
+        value.clone(),
+        paren.clone(),
+    ))?)
+}
+
+fn string(value: &Literal, paren: &Token) -> Result<String, Unwind> {
+    Ok(String::try_from(LiteralWithToken(
+        value.clone(),
+        paren.clone(),
+    ))?)
+}
+
+fn array(value: &Literal, paren: &Token) -> Result<Rc<RefCell<Vec<Literal>>>, Unwind> {
+    Ok(Rc::<RefCell<Vec<Literal>>>::try_from(LiteralWithToken(
+        value.clone(),
+        paren.clone(),
+    ))?)
+}
+
+thread_local! {
+    // Every Object is an instance of this one struct. It declares no fields, each instance has
+    // its own keys.
+    static OBJECT: Rc<StructType> = Rc::new(StructType {
+        name: "Object".to_string(),
+        fields: Vec::new(),
+    });
+}
+
+// What the runtime uses for a map: an Object with the keys as its fields, in order. Keys that
+// aren't identifiers can still be got at by indexing.
+fn object(members: Vec<(String, Literal)>) -> Literal {
+    let structure = OBJECT.with(Rc::clone);
+    let fields = members.into_iter().collect();
+
+    Literal::Instance(Rc::new(RefCell::new(Instance { structure, fields })))
+}
@@ -0,0 +1,50 @@
+use super::{function, number, string};
+use crate::parser::expr::Literal;
+use crate::parser::interpreter::Interpreter;
+use crate::parser::module::Module;
+use crate::parser::runtime_error::RuntimeError;
+use std::cell::RefCell;
+use std::env;
+use std::process;
+use std::rc::Rc;
+
+// `args` is whatever came after the script on the command line, see Interpreter::set_args.
+pub fn module(args: Vec<String>) -> Module {
+    let args = args.into_iter().map(Literal::String).collect();
+
+    let members = vec![
+        (
+            "args".to_string(),
+            Literal::Array(Rc::new(RefCell::new(args))),
+        ),
+        // The variable's value, or Null if it isn't set.
+        function("env", 1, |_, arguments, paren| {
+            match env::var(string(&arguments[0], paren)?) {
+                Ok(x) => Ok(Literal::String(x)),
+                Err(_) => Ok(Literal::Null),
+            }
+        }),
+        function("cwd", 0, |_, _, paren| match env::current_dir() {
+            Ok(x) => Ok(Literal::String(x.display().to_string())),
+            Err(e) => Err(RuntimeError::new(
+                paren,
+                format!("Couldn't get the current directory: {}.", e),
+            )
+            .with_code("E0210")
+            .into()),
+        }),
+        // Stops straight away, without running any finally blocks or waiting for tasks.
+        function("exit", 1, |_, arguments, paren| {
+            process::exit(number(&arguments[0], paren)? as i32)
+        }),
+    ];
+
+    Module::native("os", members)
+}
+
+impl Interpreter {
+    pub fn set_args(&self, args: Vec<String>) {
+        let os = Literal::Module(Rc::new(module(args)));
+        self.globals().borrow_mut().define("os".to_string(), os);
+    }
+}
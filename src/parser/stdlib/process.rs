@@ -0,0 +1,63 @@
+use super::{array, function_with_optional, string};
+use crate::parser::expr::Literal;
+use crate::parser::instance::{Instance, StructType};
+use crate::parser::module::Module;
+use crate::parser::runtime_error::RuntimeError;
+use indexmap::IndexMap;
+use std::cell::RefCell;
+use std::process::Command;
+use std::rc::Rc;
+
+pub fn module() -> Module {
+    let output = Rc::new(StructType {
+        name: "Output".to_string(),
+        fields: vec![
+            "stdout".to_string(),
+            "stderr".to_string(),
+            "status".to_string(),
+        ],
+    });
+
+    let members = vec![
+        ("Output".to_string(), Literal::Struct(output.clone())),
+        // Runs the program with the arguments, not through a shell, and waits for it to finish.
+        // The status is Null if it was killed by a signal.
+        function_with_optional("run", 1, 1, move |_, arguments, paren| {
+            let program = string(&arguments[0], paren)?;
+            let mut command = Command::new(&program);
+            if !matches!(arguments[1], Literal::Null) {
+                for argument in array(&arguments[1], paren)?.borrow().iter() {
+                    command.arg(string(argument, paren)?);
+                }
+            }
+
+            let result = command.output().map_err(|e| {
+                RuntimeError::new(paren, format!("Couldn't run '{}': {}.", program, e))
+                    .with_code("E0210")
+            })?;
+
+            let status = match result.status.code() {
+                Some(x) => Literal::Number(x as f64),
+                None => Literal::Null,
+            };
+            let fields = IndexMap::from([
+                (
+                    "stdout".to_string(),
+                    Literal::String(String::from_utf8_lossy(&result.stdout).to_string()),
+                ),
+                (
+                    "stderr".to_string(),
+                    Literal::String(String::from_utf8_lossy(&result.stderr).to_string()),
+                ),
+                ("status".to_string(), status),
+            ]);
+
+            Ok(Literal::Instance(Rc::new(RefCell::new(Instance {
+                structure: output.clone(),
+                fields,
+            }))))
+        }),
+    ];
+
+    Module::native("process", members)
+}
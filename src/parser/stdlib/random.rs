@@ -0,0 +1,146 @@
+use super::{array, function, number};
+use crate::parser::expr::Literal;
+use crate::parser::module::Module;
+use crate::parser::runtime_error::{RuntimeError, Unwind};
+use crate::scanner::token::Token;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// xoshiro256**, written out here so a seed gives the same numbers on every platform
+// and every version of every dependency.
+struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    // The seed goes through splitmix64 first, as xoshiro's authors suggest, so that small
+    // seeds like 1 and 2 still start from very different states.
+    fn new(seed: u64) -> Rng {
+        let mut x = seed;
+        let mut state = [0; 4];
+        for part in state.iter_mut() {
+            x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            *part = z ^ (z >> 31);
+        }
+        Rng { state }
+    }
+
+    fn next(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+
+    // In [0, 1), from the top 53 bits since that's all an f64 can hold.
+    fn float(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // In [0, bound), throwing away the values that would make the low numbers more likely.
+    fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let x = self.next();
+            if x < zone {
+                return x % bound;
+            }
+        }
+    }
+}
+
+// Every interpreter gets its own generator, seeded from the time until the script calls seed().
+pub fn module() -> Module {
+    let seed = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(x) => x.as_nanos() as u64,
+        Err(_) => 0,
+    };
+    let rng = Rc::new(RefCell::new(Rng::new(seed)));
+
+    let members = vec![
+        function("seed", 1, {
+            let rng = rng.clone();
+            move |_, arguments, paren| {
+                let seed = number(&arguments[0], paren)?;
+                *rng.borrow_mut() = Rng::new(seed.to_bits());
+                Ok(Literal::Null)
+            }
+        }),
+        function("random", 0, {
+            let rng = rng.clone();
+            move |_, _, _| Ok(Literal::Number(rng.borrow_mut().float()))
+        }),
+        // A whole number from lo to hi, both included.
+        function("int", 2, {
+            let rng = rng.clone();
+            move |_, arguments, paren| {
+                let lo = whole_number(&arguments[0], paren)?;
+                let hi = whole_number(&arguments[1], paren)?;
+                if lo > hi {
+                    return Err(RuntimeError::new(
+                        paren,
+                        format!("The range {} to {} is empty.", lo, hi),
+                    )
+                    .with_code("E0211")
+                    .into());
+                }
+
+                let offset = rng.borrow_mut().below((hi - lo) as u64 + 1);
+                Ok(Literal::Number((lo + offset as i64) as f64))
+            }
+        }),
+        function("choice", 1, {
+            let rng = rng.clone();
+            move |_, arguments, paren| {
+                let array = array(&arguments[0], paren)?;
+                let array = array.borrow();
+                if array.is_empty() {
+                    return Err(
+                        RuntimeError::new(paren, "Can't choose from an empty array.")
+                            .with_code("E0211")
+                            .into(),
+                    );
+                }
+
+                let index = rng.borrow_mut().below(array.len() as u64);
+                Ok(array[index as usize].clone())
+            }
+        }),
+        // Shuffles the array in place, with Fisher-Yates.
+        function("shuffle", 1, move |_, arguments, paren| {
+            let array = array(&arguments[0], paren)?;
+            let mut array = array.borrow_mut();
+            for i in (1..array.len()).rev() {
+                let j = rng.borrow_mut().below(i as u64 + 1);
+                array.swap(i, j as usize);
+            }
+            Ok(Literal::Null)
+        }),
+    ];
+
+    Module::native("random", members)
+}
+
+fn whole_number(value: &Literal, paren: &Token) -> Result<i64, Unwind> {
+    let x = number(value, paren)?;
+    match x.fract() == 0.0 && x.abs() < (1u64 << 53) as f64 {
+        true => Ok(x as i64),
+        false => Err(
+            RuntimeError::new(paren, format!("Expected a whole number, got {}.", x))
+                .with_code("E0211")
+                .into(),
+        ),
+    }
+}
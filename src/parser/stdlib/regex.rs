@@ -0,0 +1,187 @@
+use super::{function, object, string};
+use crate::parser::expr::Literal;
+use crate::parser::module::Module;
+use crate::parser::native::NativeFunction;
+use crate::parser::runtime_error::{RuntimeError, Unwind};
+use crate::scanner::token::Token;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+// A compiled pattern. Its methods are the module's functions with the pattern already filled in.
+pub struct Regex {
+    regex: ::regex::Regex,
+}
+
+pub fn module() -> Module {
+    let members = vec![
+        function("compile", 1, |_, arguments, paren| {
+            Ok(Literal::Regex(Rc::new(Regex::compile(
+                &arguments[0],
+                paren,
+            )?)))
+        }),
+        function("is_match", 2, |_, arguments, paren| {
+            Regex::compile(&arguments[0], paren)?.is_match(&arguments[1], paren)
+        }),
+        function("find", 2, |_, arguments, paren| {
+            Regex::compile(&arguments[0], paren)?.find(&arguments[1], paren)
+        }),
+        function("find_all", 2, |_, arguments, paren| {
+            Regex::compile(&arguments[0], paren)?.find_all(&arguments[1], paren)
+        }),
+        function("captures", 2, |_, arguments, paren| {
+            Regex::compile(&arguments[0], paren)?.captures(&arguments[1], paren)
+        }),
+        function("replace", 3, |_, arguments, paren| {
+            Regex::compile(&arguments[0], paren)?.replace(&arguments[1], &arguments[2], paren)
+        }),
+    ];
+
+    Module::native("regex", members)
+}
+
+impl Regex {
+    // Takes a pattern string, or a Regex that's already been compiled.
+    fn compile(pattern: &Literal, paren: &Token) -> Result<Regex, Unwind> {
+        if let Literal::Regex(x) = pattern {
+            return Ok(Regex {
+                regex: x.regex.clone(),
+            });
+        }
+
+        let pattern = string(pattern, paren)?;
+        match ::regex::Regex::new(&pattern) {
+            Ok(regex) => Ok(Regex { regex }),
+            Err(e) => Err(RuntimeError::new(
+                paren,
+                format!("Invalid regex '{}': {}.", pattern, reason(&e)),
+            )
+            .with_code("E0211")
+            .into()),
+        }
+    }
+
+    pub fn get(regex: &Rc<Regex>, name: &Token) -> Option<Literal> {
+        let method = |arity, f: fn(&Regex, &[Literal], &Token) -> Result<Literal, Unwind>| {
+            let regex = regex.clone();
+            let native = NativeFunction::new(&name.lexeme, arity, move |_, arguments, paren| {
+                f(&regex, &arguments, paren)
+            });
+            Some(Literal::Native(Rc::new(native)))
+        };
+
+        match name.lexeme.as_str() {
+            "pattern" => Some(Literal::String(regex.regex.as_str().to_string())),
+            "is_match" => method(1, |x, arguments, paren| x.is_match(&arguments[0], paren)),
+            "find" => method(1, |x, arguments, paren| x.find(&arguments[0], paren)),
+            "find_all" => method(1, |x, arguments, paren| x.find_all(&arguments[0], paren)),
+            "captures" => method(1, |x, arguments, paren| x.captures(&arguments[0], paren)),
+            "replace" => method(2, |x, arguments, paren| {
+                x.replace(&arguments[0], &arguments[1], paren)
+            }),
+            _ => None,
+        }
+    }
+
+    fn is_match(&self, text: &Literal, paren: &Token) -> Result<Literal, Unwind> {
+        match self.regex.is_match(&string(text, paren)?) {
+            true => Ok(Literal::True),
+            false => Ok(Literal::False),
+        }
+    }
+
+    // The first match, or Null if there isn't one.
+    fn find(&self, text: &Literal, paren: &Token) -> Result<Literal, Unwind> {
+        let text = string(text, paren)?;
+        match self.regex.find(&text) {
+            Some(x) => Ok(found(&text, x)),
+            None => Ok(Literal::Null),
+        }
+    }
+
+    fn find_all(&self, text: &Literal, paren: &Token) -> Result<Literal, Unwind> {
+        let text = string(text, paren)?;
+        let matches = self
+            .regex
+            .find_iter(&text)
+            .map(|x| found(&text, x))
+            .collect();
+        Ok(Literal::Array(Rc::new(RefCell::new(matches))))
+    }
+
+    // Every group by number, "0" being the whole match, and the named ones by name as well.
+    // Groups that didn't take part in the match are Null. Null if there's no match at all.
+    fn captures(&self, text: &Literal, paren: &Token) -> Result<Literal, Unwind> {
+        let text = string(text, paren)?;
+        let captures = match self.regex.captures(&text) {
+            Some(x) => x,
+            None => return Ok(Literal::Null),
+        };
+
+        let group = |x: Option<::regex::Match>| match x {
+            Some(x) => Literal::String(x.as_str().to_string()),
+            None => Literal::Null,
+        };
+        let mut groups: Vec<(String, Literal)> = (0..captures.len())
+            .map(|i| (i.to_string(), group(captures.get(i))))
+            .collect();
+        for name in self.regex.capture_names().flatten() {
+            groups.push((name.to_string(), group(captures.name(name))));
+        }
+
+        Ok(object(groups))
+    }
+
+    // Replaces every match. $1 or ${name} in the replacement is what that group matched.
+    fn replace(
+        &self,
+        text: &Literal,
+        replacement: &Literal,
+        paren: &Token,
+    ) -> Result<Literal, Unwind> {
+        let text = string(text, paren)?;
+        let replacement = string(replacement, paren)?;
+        let replaced = self.regex.replace_all(&text, replacement.as_str());
+        Ok(Literal::String(replaced.to_string()))
+    }
+}
+
+// The regex crate draws the pattern with a caret under the problem, which doesn't fit in one
+// line of an error, so this is just the last line that says what's wrong.
+fn reason(error: &::regex::Error) -> String {
+    let message = error.to_string();
+    let last = message.lines().last().unwrap_or_default();
+    last.trim_start_matches("error: ").to_string()
+}
+
+// A match's text and where it is, counted in characters rather than bytes.
+fn found(text: &str, x: ::regex::Match) -> Literal {
+    let start = text[..x.start()].chars().count();
+    let end = start + x.as_str().chars().count();
+
+    object(vec![
+        ("text".to_string(), Literal::String(x.as_str().to_string())),
+        ("start".to_string(), Literal::Number(start as f64)),
+        ("end".to_string(), Literal::Number(end as f64)),
+    ])
+}
+
+// Regexes are only ever equal to themselves.
+impl PartialEq for Regex {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<regex {}>", self.regex.as_str())
+    }
+}
@@ -0,0 +1,231 @@
+use super::{function, number, string};
+use crate::parser::expr::Literal;
+use crate::parser::interpreter::Interpreter;
+use crate::parser::module::Module;
+use crate::parser::native::NativeFunction;
+use crate::parser::runtime_error::{RuntimeError, Unwind};
+use crate::parser::task;
+use crate::scanner::token::Token;
+use std::rc::Rc;
+
+const MS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+
+pub fn module() -> Module {
+    let members = vec![
+        // Milliseconds since the Unix epoch.
+        function("now", 0, |interpreter, _, _| {
+            let now = interpreter.event_loop().borrow().clock.wall_time();
+            Ok(Literal::Number(now))
+        }),
+        function("clock", 0, clock),
+        (
+            "sleep".to_string(),
+            Literal::Native(Rc::new(NativeFunction::new("sleep", 1, task::sleep))),
+        ),
+        // Milliseconds since the epoch to an ISO-8601 string in UTC, like 2024-02-29T13:05:09.250Z.
+        // Only the years `parse` reads back, so anything too big for an i64 is an error too.
+        function("format", 1, |_, arguments, paren| {
+            let time = number(&arguments[0], paren)?.floor();
+            let first = days_from_civil(0, 1, 1) * MS_PER_DAY;
+            let last = days_from_civil(10000, 1, 1) * MS_PER_DAY - 1;
+            if !(first as f64..=last as f64).contains(&time) {
+                return Err(RuntimeError::new(
+                    paren,
+                    format!(
+                        "Can't format {} as a date, it isn't in the years 0000 to 9999.",
+                        time
+                    ),
+                )
+                .with_code("E0211")
+                .into());
+            }
+            Ok(Literal::String(format_iso(time as i64)))
+        }),
+        // The other way round. The time and offset are optional, without an offset it's UTC.
+        function("parse", 1, |_, arguments, paren| {
+            let text = string(&arguments[0], paren)?;
+            match parse_iso(&text) {
+                Some(x) => Ok(Literal::Number(x as f64)),
+                None => Err(RuntimeError::new(
+                    paren,
+                    format!("'{}' isn't an ISO-8601 date.", text),
+                )
+                .with_code("E0211")
+                .into()),
+            }
+        }),
+    ];
+
+    Module::native("time", members)
+}
+
+// Seconds since the interpreter started, only good for measuring how long something took.
+pub fn clock(interpreter: &Interpreter, _: Vec<Literal>, _: &Token) -> Result<Literal, Unwind> {
+    let now = interpreter.event_loop().borrow().clock.now();
+    Ok(Literal::Number(now / 1000.0))
+}
+
+fn format_iso(time: i64) -> String {
+    let days = time.div_euclid(MS_PER_DAY);
+    let ms = time.rem_euclid(MS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+// YYYY-MM-DD, then optionally THH:MM[:SS[.fff]] and Z or an offset like +02:00.
+fn parse_iso(text: &str) -> Option<i64> {
+    let mut cursor = Cursor { text, position: 0 };
+
+    let year = cursor.digits(4)?;
+    cursor.expect('-')?;
+    let month = cursor.digits(2)?;
+    cursor.expect('-')?;
+    let day = cursor.digits(2)?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let mut ms = 0;
+    if cursor.eat('T') || cursor.eat(' ') {
+        let hour = cursor.digits(2)?;
+        cursor.expect(':')?;
+        let minute = cursor.digits(2)?;
+        let second = match cursor.eat(':') {
+            true => cursor.digits(2)?,
+            false => 0,
+        };
+        let fraction = match cursor.eat('.') {
+            true => cursor.fraction()?,
+            false => 0,
+        };
+        if hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        ms = ((hour * 60 + minute) * 60 + second) * 1000 + fraction;
+
+        if !cursor.eat('Z') {
+            if let Some(sign) = cursor.sign() {
+                let hours = cursor.digits(2)?;
+                cursor.eat(':');
+                let minutes = cursor.digits(2)?;
+                ms -= sign * (hours * 60 + minutes) * 60_000;
+            }
+        }
+    }
+
+    match cursor.position == text.len() {
+        true => Some(days_from_civil(year, month, day) * MS_PER_DAY + ms),
+        false => None,
+    }
+}
+
+struct Cursor<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl Cursor<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        match self.peek() == Some(c) {
+            true => {
+                self.position += c.len_utf8();
+                true
+            }
+            false => false,
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Option<()> {
+        self.eat(c).then_some(())
+    }
+
+    fn sign(&mut self) -> Option<i64> {
+        if self.eat('+') {
+            return Some(1);
+        }
+        self.eat('-').then_some(-1)
+    }
+
+    // Exactly `count` digits.
+    fn digits(&mut self, count: usize) -> Option<i64> {
+        let digits = self.text.get(self.position..self.position + count)?;
+        if !digits.bytes().all(|x| x.is_ascii_digit()) {
+            return None;
+        }
+        self.position += count;
+        digits.parse().ok()
+    }
+
+    // Fractions of a second in milliseconds, anything past them is dropped.
+    fn fraction(&mut self) -> Option<i64> {
+        let rest = &self.text[self.position..];
+        let length = rest.bytes().take_while(|x| x.is_ascii_digit()).count();
+        if length == 0 {
+            return None;
+        }
+        self.position += length;
+
+        let ms = format!("{:0<3}", &rest[..length.min(3)]);
+        ms.parse().ok()
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 from a date and back, from Howard Hinnant's chrono-compatible
+// date algorithms. Eras are the 400 year cycles the Gregorian calendar repeats in.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+
+    match month <= 2 {
+        true => (year_of_era + era * 400 + 1, month, day),
+        false => (year_of_era + era * 400, month, day),
+    }
+}
@@ -1,20 +1,231 @@
-use crate::scanner::token::Token;
-use std::fmt;
+use super::expr::Expr;
+use super::pattern::Pattern;
+use crate::scanner::token::{Span, Token};
+use std::rc::Rc;
 
 pub trait StmtVisitor<T> {
-    fn visit_stmt(&mut self, stmt: &mut Stmt) -> T {
+    fn visit_stmt(&mut self, stmt: &Stmt) -> T {
         match stmt {
-            _ => todo!() // TODO
+            Stmt::Expression(x) => self.visit_expression(x),
+            Stmt::Let(x) => self.visit_let(x),
+            Stmt::Struct(x) => self.visit_struct(x),
+            Stmt::Enum(x) => self.visit_enum(x),
+            Stmt::Block(x) => self.visit_block(x),
+            Stmt::Print(x) => self.visit_print(x),
+            Stmt::Throw(x) => self.visit_throw(x),
+            Stmt::Try(x) => self.visit_try(x),
+            Stmt::Function(x) => self.visit_function(x),
+            Stmt::Return(x) => self.visit_return(x),
+            Stmt::If(x) => self.visit_if(x),
+            Stmt::While(x) => self.visit_while(x),
+            Stmt::For(x) => self.visit_for(x),
+            Stmt::Yield(x) => self.visit_yield(x),
+            Stmt::Import(x) => self.visit_import(x),
+            Stmt::Export(x) => self.visit_export(x),
         }
     }
+    fn visit_expression(&self, expression: &Expression) -> T;
+    fn visit_let(&self, let_stmt: &Let) -> T;
+    fn visit_struct(&self, struct_stmt: &Struct) -> T;
+    fn visit_enum(&self, enum_stmt: &Enum) -> T;
+    fn visit_block(&self, block: &Block) -> T;
+    fn visit_print(&self, print: &Print) -> T;
+    fn visit_throw(&self, throw: &Throw) -> T;
+    fn visit_try(&self, try_stmt: &Try) -> T;
+    fn visit_function(&self, function: &Rc<Function>) -> T;
+    fn visit_return(&self, return_stmt: &Return) -> T;
+    fn visit_if(&self, if_stmt: &If) -> T;
+    fn visit_while(&self, while_stmt: &While) -> T;
+    fn visit_for(&self, for_stmt: &For) -> T;
+    fn visit_yield(&self, yield_stmt: &Yield) -> T;
+    fn visit_import(&self, import: &Import) -> T;
+    fn visit_export(&self, export: &Export) -> T;
 }
 
 #[derive(Debug)]
 pub enum Stmt {
+    Expression(Expression),
+    Let(Let),
+    Struct(Struct),
+    Enum(Enum),
+    Block(Block),
+    Print(Print),
+    Throw(Throw),
+    Try(Try),
+    // Shared with every Function value made from it.
+    Function(Rc<Function>),
+    Return(Return),
+    If(If),
+    While(While),
+    For(Box<For>),
+    Yield(Yield),
+    Import(Import),
+    Export(Export),
 }
 
 impl Stmt {
-    pub fn accept<T>(&mut self, mut visitor: Box<dyn StmtVisitor<T>>) -> T {
+    pub fn accept<T>(&self, mut visitor: Box<dyn StmtVisitor<T>>) -> T {
         visitor.visit_stmt(self)
     }
-}
\ No newline at end of file
+
+    // All of the source the statement was parsed from.
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Expression(x) => x.span,
+            Stmt::Let(x) => x.span,
+            Stmt::Struct(x) => x.span,
+            Stmt::Enum(x) => x.span,
+            Stmt::Block(x) => x.span,
+            Stmt::Print(x) => x.span,
+            Stmt::Throw(x) => x.span,
+            Stmt::Try(x) => x.span,
+            Stmt::Function(x) => x.span,
+            Stmt::Return(x) => x.span,
+            Stmt::If(x) => x.span,
+            Stmt::While(x) => x.span,
+            Stmt::For(x) => x.span,
+            Stmt::Yield(x) => x.span,
+            Stmt::Import(x) => x.span,
+            Stmt::Export(x) => x.span,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Expression {
+    pub expression: Expr,
+    pub span: Span,
+}
+
+// `let a, [b, c] = 1, array` binds each pattern to the value in the same position.
+// Without initialisers, every pattern is bound to Null.
+#[derive(Debug)]
+pub struct Let {
+    pub keyword: Token,
+    pub patterns: Vec<Pattern>,
+    pub initialisers: Vec<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Struct {
+    pub name: Token,
+    pub fields: Vec<Token>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Enum {
+    pub name: Token,
+    pub variants: Vec<EnumVariant>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct EnumVariant {
+    pub name: Token,
+    pub fields: Vec<Token>,
+}
+
+#[derive(Debug)]
+pub struct Block {
+    pub statements: Vec<Stmt>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Print {
+    pub expression: Expr,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Throw {
+    pub keyword: Token,
+    pub value: Expr,
+    pub span: Span,
+}
+
+// At least one of catch_block or finally_block is always there.
+#[derive(Debug)]
+pub struct Try {
+    pub try_block: Vec<Stmt>,
+    pub catch_name: Option<Token>,
+    pub catch_block: Option<Vec<Stmt>>,
+    pub finally_block: Option<Vec<Stmt>>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Function {
+    pub name: Token,
+    pub params: Vec<Token>,
+    pub body: Vec<Stmt>,
+    pub kind: FunctionKind,
+    pub span: Span,
+}
+
+// Calling a generator (`fn*`) or an `async fn` doesn't run the body,
+// it gives back a Generator or Task that does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FunctionKind {
+    Function,
+    Generator,
+    Async,
+}
+
+#[derive(Debug)]
+pub struct Return {
+    pub keyword: Token,
+    pub value: Option<Expr>,
+    pub span: Span,
+}
+
+// Bodies are always blocks, and `else if` is an else block holding just the next If.
+#[derive(Debug)]
+pub struct If {
+    pub condition: Expr,
+    pub then_branch: Vec<Stmt>,
+    pub else_branch: Option<Vec<Stmt>>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct While {
+    pub condition: Expr,
+    pub body: Vec<Stmt>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct For {
+    pub keyword: Token,
+    pub pattern: Pattern,
+    pub iterable: Expr,
+    pub body: Vec<Stmt>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Yield {
+    pub keyword: Token,
+    pub value: Option<Expr>,
+    pub span: Span,
+}
+
+// Without names, the whole module is bound to the file name, `import "lib/strings"` binds `strings`.
+#[derive(Debug)]
+pub struct Import {
+    pub keyword: Token,
+    pub names: Option<Vec<Token>>,
+    pub path: String,
+    pub span: Span,
+}
+
+// Always a let, fn, struct or enum declaration at the top level.
+#[derive(Debug)]
+pub struct Export {
+    pub keyword: Token,
+    pub declaration: Box<Stmt>,
+    pub span: Span,
+}
@@ -0,0 +1,346 @@
+use super::clock::Clock;
+use super::expr::{Await, Literal};
+use super::function::Function;
+use super::interpreter::{type_error, Interpreter, LiteralWithToken};
+use super::resume::{Flow, Frame};
+use super::runtime_error::{RuntimeError, Unwind};
+use crate::scanner::token::Token;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::mem;
+use std::rc::Rc;
+
+// Calling an async fn gives back a Task that hasn't started yet, and so does sleep().
+// A task starts the first time it's awaited or spawned.
+pub struct Task {
+    kind: TaskKind,
+    state: TaskState,
+    // Tasks waiting for this one to finish.
+    waiters: Vec<Rc<RefCell<Task>>>,
+    // Spawned tasks that fail without anything awaiting them still get reported.
+    awaited: bool,
+}
+
+enum TaskKind {
+    // The body runs a bit at a time, like a generator's, see resume.rs.
+    Function {
+        function: Rc<Function>,
+        // The arguments, until the first run binds them.
+        arguments: Vec<(String, Literal)>,
+        frames: Vec<Frame>,
+    },
+    // Never runs, its timer finishes it.
+    Sleep(f64),
+}
+
+enum TaskState {
+    NotStarted,
+    // Queued to run, or waiting on a timer or another task.
+    Pending,
+    Running,
+    Done(Result<Literal, Unwind>),
+}
+
+// Single threaded, only one task runs at a time and it only stops at an await.
+pub struct EventLoop {
+    pub clock: Clock,
+    ready: VecDeque<Rc<RefCell<Task>>>,
+    // When each sleeping task finishes, in the order they started sleeping.
+    timers: Vec<(f64, Rc<RefCell<Task>>)>,
+    spawned: Vec<Rc<RefCell<Task>>>,
+    current: Option<Rc<RefCell<Task>>>,
+}
+
+impl Task {
+    pub fn new(function: Rc<Function>, arguments: Vec<(String, Literal)>) -> Task {
+        Task::with_kind(TaskKind::Function {
+            function,
+            arguments,
+            frames: Vec::new(),
+        })
+    }
+
+    fn with_kind(kind: TaskKind) -> Task {
+        Task {
+            kind,
+            state: TaskState::NotStarted,
+            waiters: Vec::new(),
+            awaited: false,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, TaskState::Done(_))
+    }
+
+    fn result(&self) -> Result<Literal, Unwind> {
+        match &self.state {
+            TaskState::Done(x) => x.clone(),
+            _ => unreachable!("only finished tasks have a result"),
+        }
+    }
+}
+
+impl EventLoop {
+    pub fn new(clock: Clock) -> EventLoop {
+        EventLoop {
+            clock,
+            ready: VecDeque::new(),
+            timers: Vec::new(),
+            spawned: Vec::new(),
+            current: None,
+        }
+    }
+
+    // Takes the timer that finishes first, ties go to whichever started sleeping first.
+    fn next_timer(&mut self) -> Option<(f64, Rc<RefCell<Task>>)> {
+        let mut next: Option<usize> = None;
+        for (index, (time, _)) in self.timers.iter().enumerate() {
+            if next.is_none_or(|x| *time < self.timers[x].0) {
+                next = Some(index);
+            }
+        }
+
+        next.map(|x| self.timers.remove(x))
+    }
+}
+
+impl Interpreter {
+    // Runs every task until they've all finished or are stuck waiting on each other,
+    // then reports any spawned task that failed without anything awaiting it.
+    pub fn run_event_loop(&self) -> Result<(), Vec<RuntimeError>> {
+        if let Err(e) = self.run_until(None) {
+            return Err(vec![e.into_error()]);
+        }
+
+        let spawned = mem::take(&mut self.event_loop().borrow_mut().spawned);
+        let mut failed = Vec::new();
+        for task in spawned {
+            let task = task.borrow();
+            if let (false, TaskState::Done(Err(e))) = (task.awaited, &task.state) {
+                failed.push(e.clone().into_error());
+            }
+        }
+
+        match failed.is_empty() {
+            true => Ok(()),
+            false => Err(failed),
+        }
+    }
+
+    // What an await is waiting for, checked before anything starts waiting on it.
+    pub(super) fn awaited_task(&self, await_expr: &Await) -> Result<Rc<RefCell<Task>>, Unwind> {
+        match self.evaluate(&await_expr.value)? {
+            Literal::Task(task) => {
+                if let Some(current) = &self.event_loop().borrow().current {
+                    if Rc::ptr_eq(current, &task) {
+                        return Err(RuntimeError::new(
+                            &await_expr.keyword,
+                            "A task can't await itself.",
+                        )
+                        .into());
+                    }
+                }
+
+                task.borrow_mut().awaited = true;
+                Ok(task)
+            }
+            x => Err(RuntimeError::new(
+                &await_expr.keyword,
+                format!("Can only await tasks, got {}.", Literal::type_name(&x)),
+            )
+            .with_code("E0202")
+            .into()),
+        }
+    }
+
+    // Awaiting at the top level runs the event loop right there, until the task is done.
+    pub(super) fn block_on(
+        &self,
+        task: &Rc<RefCell<Task>>,
+        keyword: &Token,
+    ) -> Result<Literal, Unwind> {
+        self.start_task(task);
+        self.run_until(Some((task, keyword)))?;
+        let result = task.borrow().result();
+        result
+    }
+
+    // Queues up a task that hasn't started yet, anything else is left alone.
+    fn start_task(&self, task: &Rc<RefCell<Task>>) {
+        let mut inner = task.borrow_mut();
+        if !matches!(inner.state, TaskState::NotStarted) {
+            return;
+        }
+        inner.state = TaskState::Pending;
+
+        let mut event_loop = self.event_loop().borrow_mut();
+        match inner.kind {
+            TaskKind::Function { .. } => event_loop.ready.push_back(task.clone()),
+            TaskKind::Sleep(ms) => {
+                let time = event_loop.clock.now() + ms;
+                event_loop.timers.push((time, task.clone()));
+            }
+        }
+    }
+
+    fn run_until(&self, until: Option<(&Rc<RefCell<Task>>, &Token)>) -> Result<(), Unwind> {
+        loop {
+            if let Some((task, _)) = until {
+                if task.borrow().is_done() {
+                    return Ok(());
+                }
+            }
+
+            let ready = self.event_loop().borrow_mut().ready.pop_front();
+            if let Some(task) = ready {
+                self.run_task(&task);
+                continue;
+            }
+
+            let timer = self.event_loop().borrow_mut().next_timer();
+            match (timer, until) {
+                (Some((time, task)), _) => {
+                    self.event_loop().borrow_mut().clock.wait_until(time);
+                    self.finish_task(&task, Ok(Literal::Null));
+                }
+                (None, Some((_, keyword))) => {
+                    return Err(RuntimeError::new(
+                        keyword,
+                        "Every task is waiting on another one, so this will never finish.",
+                    )
+                    .into())
+                }
+                (None, None) => return Ok(()),
+            }
+        }
+    }
+
+    // Runs the task up to its next await, or to the end.
+    fn run_task(&self, task: &Rc<RefCell<Task>>) {
+        let (function, mut frames, arguments) = {
+            let mut inner = task.borrow_mut();
+            inner.state = TaskState::Running;
+            match &mut inner.kind {
+                TaskKind::Function {
+                    function,
+                    arguments,
+                    frames,
+                } => (function.clone(), mem::take(frames), mem::take(arguments)),
+                TaskKind::Sleep(_) => unreachable!("sleeping tasks are finished by their timer"),
+            }
+        };
+
+        self.event_loop().borrow_mut().current = Some(task.clone());
+        let closure = self.with_environment(function.closure.clone());
+        let result = closure.resume_block(&function.declaration.body, &mut frames, 0, arguments);
+        self.event_loop().borrow_mut().current = None;
+
+        match result {
+            Ok(Flow::Suspend(Literal::Task(awaited))) => {
+                let mut inner = task.borrow_mut();
+                inner.state = TaskState::Pending;
+                if let TaskKind::Function { frames: x, .. } = &mut inner.kind {
+                    *x = frames;
+                }
+                drop(inner);
+
+                self.start_task(&awaited);
+                if awaited.borrow().is_done() {
+                    let result = awaited.borrow().result();
+                    self.wake_task(task, result);
+                } else {
+                    awaited.borrow_mut().waiters.push(task.clone());
+                }
+            }
+            Ok(Flow::Suspend(_)) => unreachable!("tasks only suspend to await another task"),
+            Ok(Flow::Done) => self.finish_task(task, Ok(Literal::Null)),
+            Err(Unwind::Return(x)) => self.finish_task(task, Ok(x)),
+            Err(x) => self.finish_task(task, Err(x)),
+        }
+    }
+
+    fn finish_task(&self, task: &Rc<RefCell<Task>>, result: Result<Literal, Unwind>) {
+        let waiters = {
+            let mut inner = task.borrow_mut();
+            inner.state = TaskState::Done(result.clone());
+            mem::take(&mut inner.waiters)
+        };
+
+        for waiter in waiters {
+            self.wake_task(&waiter, result.clone());
+        }
+    }
+
+    // Hands a waiting task what it was waiting on finished with, and queues it up to carry on.
+    fn wake_task(&self, task: &Rc<RefCell<Task>>, result: Result<Literal, Unwind>) {
+        if let TaskKind::Function { frames, .. } = &mut task.borrow_mut().kind {
+            if let Some(Frame::Await(x)) = frames.last_mut() {
+                *x = Some(result);
+            }
+        }
+
+        self.event_loop().borrow_mut().ready.push_back(task.clone());
+    }
+}
+
+// sleep(ms) gives back a task that finishes `ms` milliseconds after it starts.
+pub fn sleep(_: &Interpreter, arguments: Vec<Literal>, paren: &Token) -> Result<Literal, Unwind> {
+    let ms = f64::try_from(LiteralWithToken(arguments[0].clone(), paren.clone()))?;
+    if ms < 0.0 {
+        return Err(
+            RuntimeError::new(paren, "Can't sleep for a negative amount of time.")
+                .with_code("E0211")
+                .into(),
+        );
+    }
+
+    Ok(Literal::Task(Rc::new(RefCell::new(Task::with_kind(
+        TaskKind::Sleep(ms),
+    )))))
+}
+
+// spawn(task) starts a task running alongside everything else, without waiting for it.
+pub fn spawn(
+    interpreter: &Interpreter,
+    arguments: Vec<Literal>,
+    paren: &Token,
+) -> Result<Literal, Unwind> {
+    match &arguments[0] {
+        Literal::Task(task) => {
+            interpreter.start_task(task);
+            interpreter
+                .event_loop()
+                .borrow_mut()
+                .spawned
+                .push(task.clone());
+            Ok(arguments[0].clone())
+        }
+        x => Err(type_error(paren, "Task", x).into()),
+    }
+}
+
+// Tasks are only ever equal to themselves.
+impl PartialEq for Task {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for Task {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            TaskKind::Function { function, .. } => {
+                write!(f, "<task {}>", function.declaration.name.lexeme)
+            }
+            TaskKind::Sleep(ms) => write!(f, "<task sleep({})>", ms),
+        }
+    }
+}
@@ -1,6 +1,6 @@
-use super::token::Token;
+use super::token::{SourceId, Span, Token};
 use super::token_type::TokenType;
-use crate::error::{self, LogLevel};
+use crate::error::{Diagnostic, LogLevel};
 
 use lazy_static::lazy_static;
 use std::collections::HashMap;
@@ -8,10 +8,16 @@ use std::collections::HashMap;
 pub struct Scanner {
     source: String,
     tokens: Vec<Token>,
+    // Byte offsets of where the token being scanned starts and how far it's got.
     start: usize,
     current: usize,
     line: u32,
     column: u32,
+    // Where the token being scanned starts, for its span.
+    start_line: u32,
+    start_column: u32,
+    id: SourceId,
+    diagnostics: Vec<Diagnostic>,
 }
 
 fn is_ident_char(c: char) -> bool {
@@ -24,27 +30,41 @@ lazy_static! {
     static ref KEYWORDS_TABLE: HashMap<&'static str, TokenType> = {
         let mut m = HashMap::new();
         m.insert("and", TokenType::And);
+        m.insert("async", TokenType::Async);
+        m.insert("await", TokenType::Await);
+        m.insert("catch", TokenType::Catch);
         m.insert("class", TokenType::Class);
         m.insert("else", TokenType::Else);
+        m.insert("enum", TokenType::Enum);
+        m.insert("export", TokenType::Export);
         m.insert("False", TokenType::False);
+        m.insert("finally", TokenType::Finally);
         m.insert("for", TokenType::For);
         m.insert("fn", TokenType::Fn);
         m.insert("if", TokenType::If);
+        m.insert("import", TokenType::Import);
+        m.insert("in", TokenType::In);
+        m.insert("is", TokenType::Is);
+        m.insert("match", TokenType::Match);
         m.insert("Null", TokenType::Null);
         m.insert("or", TokenType::Or);
         m.insert("print", TokenType::Print);
         m.insert("return", TokenType::Return);
+        m.insert("struct", TokenType::Struct);
         m.insert("super", TokenType::Super);
         m.insert("self", TokenType::_Self);
+        m.insert("throw", TokenType::Throw);
         m.insert("True", TokenType::True);
+        m.insert("try", TokenType::Try);
         m.insert("let", TokenType::Let);
         m.insert("while", TokenType::While);
+        m.insert("yield", TokenType::Yield);
         m
     };
 }
 
 impl Scanner {
-    pub fn new(source: String) -> Scanner {
+    pub fn new(source: String, id: SourceId) -> Scanner {
         Scanner {
             source,
             tokens: Vec::new(),
@@ -52,21 +72,29 @@ impl Scanner {
             current: 0,
             line: 1,
             column: 0,
+            start_line: 1,
+            start_column: 1,
+            id,
+            diagnostics: Vec::new(),
         }
     }
 
-    pub fn scan_tokens(&mut self) -> Vec<Token> {
+    // Scans the whole source even after an error, so they all get found at once. The tokens are
+    // everything that did scan, so the parser can still look for errors in them.
+    pub fn scan_tokens(&mut self) -> (Vec<Token>, Vec<Diagnostic>) {
         while !self.is_at_end() {
             self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.column + 1;
             self.scan_token();
         }
-        self.tokens.push(Token::new(
-            TokenType::Eof,
-            "".to_string(),
-            self.line,
-            self.column,
-        ));
-        self.tokens.clone()
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_column = self.column + 1;
+        self.tokens
+            .push(Token::new(TokenType::Eof, "".to_string(), self.span()));
+
+        (self.tokens.clone(), self.diagnostics.clone())
     }
 
     fn scan_token(&mut self) {
@@ -76,8 +104,22 @@ impl Scanner {
             ')' => self.add_token(TokenType::RightParen),
             '{' => self.add_token(TokenType::LeftBrace),
             '}' => self.add_token(TokenType::RightBrace),
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
             ',' => self.add_token(TokenType::Comma),
-            '.' => self.add_token(TokenType::Dot),
+            '.' => {
+                if self.cmp('.') {
+                    if self.cmp('=') {
+                        self.add_token(TokenType::DotDotEqual)
+                    } else if self.cmp('.') {
+                        self.add_token(TokenType::DotDotDot)
+                    } else {
+                        self.add_token(TokenType::DotDot)
+                    }
+                } else {
+                    self.add_token(TokenType::Dot)
+                }
+            }
             '-' => self.add_token(TokenType::Minus),
             '+' => self.add_token(TokenType::Plus),
             ';' => self.add_token(TokenType::Semicolon),
@@ -94,6 +136,8 @@ impl Scanner {
             '=' => {
                 if self.cmp('=') {
                     self.add_token(TokenType::EqualEqual)
+                } else if self.cmp('>') {
+                    self.add_token(TokenType::FatArrow)
                 } else {
                     self.add_token(TokenType::Equal)
                 }
@@ -118,7 +162,6 @@ impl Scanner {
                     while self.peek(0) != '\n' && !self.is_at_end() {
                         self.advance();
                     }
-                    self.new_line();
                 }
                 '*' => {
                     self.advance();
@@ -131,25 +174,21 @@ impl Scanner {
             '"' => self.string(),
             '\n' => self.new_line(),
             x if x.is_whitespace() => (),
-            x if x.is_digit(10) => self.number(),
+            x if x.is_ascii_digit() => self.number(),
             x if is_ident_char(x) => self.identifier(),
-            x => {
-                self.report(LogLevel::Error, "", &format!("Unexpected character: {}", x));
-                self.advance();
-            }
+            x => self.error("E0001", &format!("Unexpected character: {}", x)),
         }
     }
 
     fn string(&mut self) {
         while self.peek(0) != '"' && !self.is_at_end() {
-            if self.peek(0) == '\n' {
+            if self.advance() == '\n' {
                 self.new_line()
             }
-            self.advance();
         }
 
         if self.is_at_end() {
-            self.report(LogLevel::Error, "", "Unterminated string.");
+            self.error("E0002", "Unterminated string.");
             return;
         }
 
@@ -160,13 +199,13 @@ impl Scanner {
     }
 
     fn number(&mut self) {
-        while self.peek(0).is_digit(10) {
+        while self.peek(0).is_ascii_digit() {
             self.advance();
         }
 
-        if self.peek(0) == '.' && self.peek(1).is_digit(10) {
+        if self.peek(0) == '.' && self.peek(1).is_ascii_digit() {
             self.advance();
-            while self.peek(0).is_digit(10) {
+            while self.peek(0).is_ascii_digit() {
                 self.advance();
             }
         }
@@ -177,7 +216,8 @@ impl Scanner {
     }
 
     fn identifier(&mut self) {
-        while is_ident_char(self.peek(0)) {
+        // Digits can't start an identifier, but they can be in the rest of it, like `log2`.
+        while is_ident_char(self.peek(0)) || self.peek(0).is_ascii_digit() {
             self.advance();
         }
 
@@ -190,11 +230,10 @@ impl Scanner {
     }
 
     fn block_comment(&mut self) {
-        while !(self.peek(0) == '*' && self.peek(1) == '/') && !self.is_at_end() {
-            if self.peek(0) == '\n' {
+        while !(self.is_at_end() || self.peek(0) == '*' && self.peek(1) == '/') {
+            if self.advance() == '\n' {
                 self.new_line();
             }
-            self.advance();
         }
         self.advance();
         self.advance();
@@ -205,8 +244,11 @@ impl Scanner {
     }
 
     fn advance(&mut self) -> char {
-        let out = self.source.chars().nth(self.current).unwrap_or('\0');
-        self.current += 1;
+        let out = match self.source[self.current..].chars().next() {
+            Some(x) => x,
+            None => return '\0',
+        };
+        self.current += out.len_utf8();
         self.column += 1;
         out
     }
@@ -214,13 +256,24 @@ impl Scanner {
     fn add_token(&mut self, token: TokenType) {
         let text = &self.source[self.start..self.current];
         self.tokens
-            .push(Token::new(token, text.to_string(), self.line, self.column));
+            .push(Token::new(token, text.to_string(), self.span()));
+    }
+
+    // The token being scanned, so far.
+    fn span(&self) -> Span {
+        Span {
+            start: self.start,
+            end: self.current,
+            line: self.start_line,
+            column: self.start_column,
+            source: self.id,
+        }
     }
 
     fn peek(&self, lookahead: usize) -> char {
-        self.source
+        self.source[self.current..]
             .chars()
-            .nth(self.current + lookahead)
+            .nth(lookahead)
             .unwrap_or('\0')
     }
 
@@ -228,7 +281,7 @@ impl Scanner {
         if self.is_at_end() || (self.peek(0) != expected) {
             return false;
         }
-        self.current += 1;
+        self.advance();
         true
     }
 
@@ -238,14 +291,9 @@ impl Scanner {
     }
 
     // Error helper
-    fn report(&self, log_level: LogLevel, location: &str, message: &str) {
-        error::report(
-            self.line,
-            self.column,
-            log_level,
-            location,
-            message,
-            &self.source,
-        );
+    fn error(&mut self, code: &'static str, message: &str) {
+        let span = self.span();
+        self.diagnostics
+            .push(Diagnostic::new(LogLevel::Error, code, message, span));
     }
 }
@@ -6,17 +6,61 @@ use std::fmt::{self, Display};
 pub struct Token {
     pub token: TokenType,
     pub lexeme: String,
+    // Where the token starts. Columns count characters from 1.
     pub line: u32,
     pub column: u32,
+    // Byte offsets into the source, end is one past the last byte.
+    pub start: usize,
+    pub end: usize,
+    pub source: SourceId,
+}
+
+// Where something is in the source, for diagnostics to point at: the bytes from start up to end,
+// and the line and column that start is on.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: u32,
+    pub column: u32,
+    // Which source the offsets are into, functions can be called from a different one.
+    pub source: SourceId,
+}
+
+// A source the interpreter has run, see error::Sources for its name and text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SourceId(pub u32);
+
+impl Span {
+    // From the start of this one to the end of the other.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: other.end.max(self.end),
+            ..self
+        }
+    }
 }
 
 impl Token {
-    pub fn new(token: TokenType, lexeme: String, line: u32, column: u32) -> Token {
+    pub fn new(token: TokenType, lexeme: String, span: Span) -> Token {
         Token {
             token,
             lexeme,
-            line,
-            column,
+            line: span.line,
+            column: span.column,
+            start: span.start,
+            end: span.end,
+            source: span.source,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        Span {
+            start: self.start,
+            end: self.end,
+            line: self.line,
+            column: self.column,
+            source: self.source,
         }
     }
 
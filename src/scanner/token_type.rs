@@ -8,6 +8,8 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
@@ -27,6 +29,10 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    FatArrow,
+    DotDot,
+    DotDotEqual,
+    DotDotDot,
 
     // Literals
     Identifier,
@@ -35,21 +41,35 @@ pub enum TokenType {
 
     // Keywords
     And,
+    Async,
+    Await,
+    Catch,
     Class,
     Else,
+    Enum,
+    Export,
     False,
+    Finally,
     Fn,
     For,
     If,
+    Import,
+    In,
+    Is,
+    Match,
     Null,
     Or,
     Print,
     Return,
+    Struct,
     Super,
     _Self,
+    Throw,
     True,
+    Try,
     Let,
     While,
+    Yield,
 
     Eof,
 }
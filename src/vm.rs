@@ -0,0 +1,320 @@
+use crate::error::{Diagnostic, Error, Sources};
+use crate::parser;
+use crate::parser::clock::Clock;
+use crate::parser::expr::Literal;
+use crate::parser::interpreter::{Interpreter, LiteralWithToken};
+use crate::parser::native::NativeFunction;
+use crate::parser::runtime_error::{RuntimeError, Unwind};
+use crate::scanner::token::Token;
+use std::cell::{Ref, RefCell};
+use std::fmt;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// An interpreter for Rust programs to run horba in.
+///
+/// ```
+/// use horba::{Literal, Vm};
+///
+/// let vm = Vm::builder()
+///     .register_fn("add", |a: f64, b: f64| a + b)
+///     .build();
+/// vm.set_global("x", 2.0);
+/// assert_eq!(vm.eval("add(x, 3)")?, Literal::Number(5.0));
+/// # Ok::<(), horba::EvalError>(())
+/// ```
+pub struct Vm {
+    interpreter: Interpreter,
+    // Warnings from everything eval has parsed, until they're taken.
+    warnings: RefCell<Vec<Diagnostic>>,
+}
+
+pub struct VmBuilder {
+    clock: Clock,
+    args: Vec<String>,
+    search_path: Vec<PathBuf>,
+    functions: Vec<NativeFunction>,
+}
+
+#[derive(Debug)]
+pub enum EvalError {
+    // The source didn't scan or parse, these are the errors and any warnings.
+    Parse(Vec<Diagnostic>),
+    Runtime(Box<RuntimeError>),
+    // Spawned tasks that nothing awaited failed after the source had finished.
+    Task(Vec<RuntimeError>),
+}
+
+impl Vm {
+    pub fn new() -> Vm {
+        Vm::builder().build()
+    }
+
+    pub fn builder() -> VmBuilder {
+        VmBuilder {
+            clock: Clock::real(),
+            args: Vec::new(),
+            search_path: Vec::new(),
+            functions: Vec::new(),
+        }
+    }
+
+    pub fn interpreter(&self) -> &Interpreter {
+        &self.interpreter
+    }
+
+    pub fn register_fn<Args, F: HostFunction<Args>>(&self, name: &str, function: F) {
+        self.interpreter
+            .define_native(host_function(name, function));
+    }
+
+    // Runs the source in the top level scope, so what it declares is there for the next eval.
+    // The value is the last statement's if it was an expression, otherwise Null.
+    // Warnings don't stop it running, they're kept for take_warnings.
+    pub fn eval(&self, source: &str) -> Result<Literal, EvalError> {
+        let id = self.interpreter.add_source("<eval>", source);
+        let program = parser::parse_source(source, id, self.interpreter.max_errors())
+            .map_err(EvalError::Parse)?;
+        self.warnings.borrow_mut().extend(program.warnings);
+
+        let mut value = Literal::Null;
+        for statement in program.statements.iter() {
+            value = self
+                .interpreter
+                .run_statement(statement)
+                .map_err(EvalError::Runtime)?;
+        }
+
+        self.interpreter.run_event_loop().map_err(EvalError::Task)?;
+        Ok(value)
+    }
+
+    // The warnings from every eval since they were last taken, whether or not it then ran
+    // without an error. A source that didn't parse has its warnings in EvalError::Parse instead.
+    pub fn take_warnings(&self) -> Vec<Diagnostic> {
+        self.warnings.take()
+    }
+
+    // Everything eval has run and imported, for error::report to show diagnostics against.
+    pub fn sources(&self) -> Ref<'_, Sources> {
+        self.interpreter.sources()
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Literal> {
+        self.interpreter.get_variable(name)
+    }
+
+    pub fn set_global(&self, name: &str, value: impl IntoLiteral) {
+        self.interpreter.set_variable(name, value.into_literal());
+    }
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VmBuilder {
+    // See Clock::new_virtual for running scripts that sleep without waiting.
+    pub fn clock(mut self, clock: Clock) -> VmBuilder {
+        self.clock = clock;
+        self
+    }
+
+    // What scripts see as os.args.
+    pub fn args(mut self, args: Vec<String>) -> VmBuilder {
+        self.args = args;
+        self
+    }
+
+    pub fn search_path(mut self, path: PathBuf) -> VmBuilder {
+        self.search_path.push(path);
+        self
+    }
+
+    pub fn register_fn<Args, F: HostFunction<Args>>(
+        mut self,
+        name: &str,
+        function: F,
+    ) -> VmBuilder {
+        self.functions.push(host_function(name, function));
+        self
+    }
+
+    pub fn build(self) -> Vm {
+        let interpreter = Interpreter::with_clock(self.clock);
+        interpreter.set_args(self.args);
+        for path in self.search_path {
+            interpreter.add_search_path(path);
+        }
+        for function in self.functions {
+            interpreter.define_native(function);
+        }
+
+        Vm {
+            interpreter,
+            warnings: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+fn host_function<Args, F: HostFunction<Args>>(name: &str, function: F) -> NativeFunction {
+    let arity = F::arity();
+    NativeFunction::new(name, arity, move |_, arguments, paren| {
+        function.call(arguments, paren)
+    })
+}
+
+// A Rust closure scripts can call. Each argument is converted with its type's
+// TryFrom<LiteralWithToken>, so a wrong type is a runtime error at the call, and the result
+// can be anything that's IntoLiteral, or a Result of one, whose Err becomes a runtime error.
+pub trait HostFunction<Args>: 'static {
+    fn arity() -> usize;
+    fn call(&self, arguments: Vec<Literal>, paren: &Token) -> Result<Literal, Unwind>;
+}
+
+macro_rules! host_function {
+    ($($arg:ident),*) => {
+        impl<F, R, $($arg),*> HostFunction<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + 'static,
+            R: HostResult,
+            $($arg: TryFrom<LiteralWithToken, Error = RuntimeError>,)*
+        {
+            fn arity() -> usize {
+                0 $(+ { let _ = stringify!($arg); 1 })*
+            }
+
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn call(&self, arguments: Vec<Literal>, paren: &Token) -> Result<Literal, Unwind> {
+                let mut arguments = arguments.into_iter();
+                $(
+                    let $arg = $arg::try_from(LiteralWithToken(
+                        arguments.next().unwrap_or(Literal::Null),
+                        paren.clone(),
+                    ))?;
+                )*
+                (self)($($arg),*).into_result(paren)
+            }
+        }
+    };
+}
+
+host_function!();
+host_function!(A);
+host_function!(A, B);
+host_function!(A, B, C);
+host_function!(A, B, C, D);
+host_function!(A, B, C, D, E);
+
+// Rust values that can be handed to scripts.
+pub trait IntoLiteral {
+    fn into_literal(self) -> Literal;
+}
+
+impl IntoLiteral for Literal {
+    fn into_literal(self) -> Literal {
+        self
+    }
+}
+
+impl IntoLiteral for f64 {
+    fn into_literal(self) -> Literal {
+        Literal::Number(self)
+    }
+}
+
+impl IntoLiteral for String {
+    fn into_literal(self) -> Literal {
+        Literal::String(self)
+    }
+}
+
+impl IntoLiteral for &str {
+    fn into_literal(self) -> Literal {
+        Literal::String(self.to_string())
+    }
+}
+
+impl IntoLiteral for bool {
+    fn into_literal(self) -> Literal {
+        match self {
+            true => Literal::True,
+            false => Literal::False,
+        }
+    }
+}
+
+impl IntoLiteral for () {
+    fn into_literal(self) -> Literal {
+        Literal::Null
+    }
+}
+
+impl<T: IntoLiteral> IntoLiteral for Option<T> {
+    fn into_literal(self) -> Literal {
+        match self {
+            Some(x) => x.into_literal(),
+            None => Literal::Null,
+        }
+    }
+}
+
+impl<T: IntoLiteral> IntoLiteral for Vec<T> {
+    fn into_literal(self) -> Literal {
+        let elements = self.into_iter().map(IntoLiteral::into_literal).collect();
+        Literal::Array(Rc::new(RefCell::new(elements)))
+    }
+}
+
+// What a host function can give back.
+pub trait HostResult {
+    fn into_result(self, paren: &Token) -> Result<Literal, Unwind>;
+}
+
+impl<T: IntoLiteral> HostResult for T {
+    fn into_result(self, _: &Token) -> Result<Literal, Unwind> {
+        Ok(self.into_literal())
+    }
+}
+
+impl<T: IntoLiteral, E: fmt::Display> HostResult for Result<T, E> {
+    fn into_result(self, paren: &Token) -> Result<Literal, Unwind> {
+        self.map(IntoLiteral::into_literal)
+            .map_err(|e| RuntimeError::new(paren, e.to_string()).into())
+    }
+}
+
+impl EvalError {
+    // Everything there is to report, for error::report or a host's own renderer.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            EvalError::Parse(x) => x.clone(),
+            EvalError::Runtime(x) => vec![x.diagnostic()],
+            EvalError::Task(x) => x.iter().map(task_diagnostic).collect(),
+        }
+    }
+}
+
+// What a failed spawned task is reported as. It keeps its own code, with a note saying why
+// it's only coming up after the script finished.
+pub fn task_diagnostic(error: &RuntimeError) -> Diagnostic {
+    let mut diagnostic = error.diagnostic();
+    diagnostic
+        .notes
+        .push("This happened in a spawned task that nothing awaited.".to_string());
+    diagnostic
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::Parse(_) => write!(f, "The source has errors."),
+            EvalError::Runtime(x) => write!(f, "{}", x),
+            EvalError::Task(_) => write!(f, "A spawned task failed."),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
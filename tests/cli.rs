@@ -0,0 +1,146 @@
+use serde_json::Value;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+// Runs horba with the flags, on a script with this source and these arguments if there is
+// one, and with this on stdin. Gives back stdout, stderr and the exit code.
+fn horba(
+    test: &str,
+    flags: &[&str],
+    script: Option<(&str, &[&str])>,
+    stdin: &str,
+) -> (String, String, i32) {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_horba"));
+    command.args(flags);
+
+    let script = script.map(|(source, args)| {
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("horba-{}-{}.hb", test, std::process::id()));
+        fs::write(&path, source).unwrap();
+        command.arg(&path).args(args);
+        path
+    });
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    if let Some(path) = script {
+        fs::remove_file(path).unwrap();
+    }
+
+    (
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+        output.status.code().unwrap(),
+    )
+}
+
+#[test]
+fn scripts_dont_print_expression_values() {
+    let source = "print 1\n2 + 3\nlet s = Set()\ns.add(1)\nprint s.size\n";
+    let (stdout, stderr, code) = horba("silent", &[], Some((source, &[])), "");
+    assert_eq!(stdout, "1\n1\n", "{}", stderr);
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn the_repl_prints_expression_values() {
+    let (stdout, _, code) = horba("repl", &[], None, "1 + 2\nlet a = 4\na\n.exit\n");
+    assert!(stdout.contains("3\n"), "{}", stdout);
+    assert!(stdout.contains("4\n"), "{}", stdout);
+    assert!(!stdout.contains("Null"), "{}", stdout);
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn scripts_read_stdin_a_line_at_a_time() {
+    let source = "let line = io.read_line()\nwhile (line != Null) {\n  io.write(line)\n  io.write(\"|\")\n  line = io.read_line()\n}\n";
+    let (stdout, stderr, code) = horba("read-line", &[], Some((source, &[])), "a\nb\r\nc");
+    assert_eq!(stdout, "a|b|c|", "{}", stderr);
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn scripts_get_their_arguments_and_exit_with_their_code() {
+    let source = "print os.args\nos.exit(3)\nprint \"not reached\"\n";
+    let (stdout, stderr, code) = horba("args", &[], Some((source, &["a", "--b"])), "");
+    assert_eq!(stdout, "[a, --b]\n", "{}", stderr);
+    assert_eq!(code, 3);
+}
+
+#[test]
+fn exit_codes_say_what_went_wrong() {
+    let run = |test, source| horba(test, &[], Some((source, &[])), "").2;
+    assert_eq!(run("exit-ok", "print 1\n"), 0);
+    assert_eq!(run("exit-syntax", "let = 1\n"), 65);
+    assert_eq!(run("exit-runtime", "print 1 / 0\n"), 70);
+    assert_eq!(run("exit-uncaught", "throw \"up\"\n"), 70);
+
+    let (_, stderr, code) = horba("exit-usage", &["--no-such-flag"], None, "");
+    assert_eq!(code, 64);
+    assert!(stderr.starts_with("Usage: horba"), "{}", stderr);
+
+    let missing = std::env::temp_dir().join("horba-surely-missing.hb");
+    let (_, stderr, code) = horba("exit-input", &[missing.to_str().unwrap()], None, "");
+    assert_eq!(code, 66);
+    assert!(stderr.starts_with("Couldn't read"), "{}", stderr);
+}
+
+// Each line of stderr as the JSON object it should be.
+fn json_lines(stderr: &str) -> Vec<Value> {
+    stderr
+        .lines()
+        .map(|x| serde_json::from_str(x).unwrap_or_else(|e| panic!("{}: {}", e, x)))
+        .collect()
+}
+
+#[test]
+fn json_errors_are_one_object_per_line() {
+    let (stdout, stderr, code) = horba(
+        "json-syntax",
+        &["--error-format=json"],
+        Some(("let = 1\nprint )\n", &[])),
+        "",
+    );
+    let errors = json_lines(&stderr);
+    assert_eq!((stdout.as_str(), code, errors.len()), ("", 65, 2));
+    assert_eq!(errors[0]["severity"], "error");
+    assert_eq!(errors[0]["code"], "E0100");
+    assert_eq!(errors[0]["message"], "Expect pattern.");
+    let file = format!("horba-json-syntax-{}.hb", std::process::id());
+    assert!(errors[0]["file"].as_str().unwrap().ends_with(&file));
+    assert_eq!(errors[1]["span"]["line_start"], 2);
+    assert_eq!(errors[1]["span"]["column_start"], 7);
+    assert_eq!(errors[1]["span"]["column_end"], 8);
+}
+
+#[test]
+fn json_warnings_carry_their_suggestions() {
+    let source = "enum E { A, B }\nlet r = match E.A {\n  E.A => 1\n}\nprint r\n";
+    let (stdout, stderr, code) = horba(
+        "json-warning",
+        &["--error-format=json"],
+        Some((source, &[])),
+        "",
+    );
+    let warnings = json_lines(&stderr);
+    assert_eq!((stdout.as_str(), code, warnings.len()), ("1\n", 0, 1));
+    assert_eq!(warnings[0]["severity"], "warning");
+    assert_eq!(warnings[0]["code"], "W0100");
+    assert_eq!(
+        warnings[0]["suggestions"][0],
+        "Add a '_' arm to match anything else."
+    );
+}
@@ -0,0 +1,62 @@
+use horba::{Literal, Vm};
+
+fn eval(source: &str) -> String {
+    Vm::new().eval(source).unwrap().to_string()
+}
+
+#[test]
+fn every_nan_is_a_new_element() {
+    let source = "let nan = math.NAN\nlet set = Set([nan, nan])\nset.add(nan)\n[set.size, set.has(nan), set.remove(nan), set.size]\n";
+    assert_eq!(eval(source), "[3, False, False, 3]");
+
+    let source = "let set = Set([math.NAN, 1])\nset.clear()\nset.size\n";
+    assert_eq!(eval(source), "0");
+}
+
+#[test]
+fn zero_and_minus_zero_are_one_element() {
+    assert_eq!(
+        eval("let set = Set([0, -0])\n[set.size, set.has(-0)]\n"),
+        "[1, True]"
+    );
+}
+
+#[test]
+fn declarations_are_keyed_by_how_theyre_declared() {
+    let source = "struct A { x, y }\nlet first = A\nstruct A { x, y }\nlet set = Set([first, A])\nstruct A { x }\nset.add(A)\nset.size\n";
+    assert_eq!(eval(source), "2");
+
+    let source = "enum E { One, Two(x) }\nlet first = E\nenum E { One, Two(x) }\nlet set = Set([first, E, first.Two, E.Two])\nenum E { One, Two(y) }\nset.add(E)\nset.add(E.Two)\nset.size\n";
+    assert_eq!(eval(source), "4");
+}
+
+#[test]
+fn arrays_cant_go_in_a_set() {
+    let error = Vm::new().eval("Set([[1]])").err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Array can't go in a Set, it could change after it's added."
+    );
+    assert_eq!(Vm::new().eval("Set().size").unwrap(), Literal::Number(0.0));
+}
+
+#[test]
+fn sets_and_deques_cant_go_in_a_set() {
+    let error = Vm::new().eval("Set().add(Set([1]))").err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Set can't go in a Set, it could change after it's added."
+    );
+    let error = Vm::new().eval("Set([Deque([1])])").err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Deque can't go in a Set, it could change after it's added."
+    );
+
+    // A priority queue is only ever equal to itself, so it can.
+    let source = "let queue = PriorityQueue()
+let set = Set([queue, PriorityQueue()])
+[set.size, set.has(queue)]
+";
+    assert_eq!(Vm::new().eval(source).unwrap().to_string(), "[2, True]");
+}
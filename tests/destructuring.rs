@@ -0,0 +1,39 @@
+use horba::{EvalError, Literal, Vm};
+
+fn eval(source: &str) -> Literal {
+    Vm::new().eval(source).unwrap()
+}
+
+#[test]
+fn let_takes_array_and_struct_patterns() {
+    let source = "let [a, b, ...rest] = [1, 2, 3, 4]\n[a, b, rest]\n";
+    assert_eq!(eval(source).to_string(), "[1, 2, [3, 4]]");
+
+    let source = "struct Point { x, y }\nlet { x, y } = Point(5, 6)\n[x, y]\n";
+    assert_eq!(eval(source).to_string(), "[5, 6]");
+}
+
+#[test]
+fn parallel_assignment_reads_every_value_first() {
+    let source = "let a = 1\nlet b = 2\na, b = b, a\n[a, b]\n";
+    assert_eq!(eval(source).to_string(), "[2, 1]");
+
+    let source = "let arr = [1, 2, 3]\narr[0], arr[2] = arr[2], arr[0]\narr\n";
+    assert_eq!(eval(source).to_string(), "[3, 2, 1]");
+}
+
+#[test]
+fn a_comma_without_an_assignment_is_still_the_comma_operator() {
+    assert_eq!(eval("(1, 2)"), Literal::Number(2.0));
+}
+
+#[test]
+fn values_that_dont_fit_the_pattern_are_errors() {
+    match Vm::new().eval("let [p] = [1, 2]\n") {
+        Err(EvalError::Runtime(error)) => {
+            assert_eq!(error.code, "E0208");
+            assert_eq!(error.message, "[1, 2] doesn't match the pattern [p].");
+        }
+        other => panic!("{:?}", other.map_err(|x| x.to_string())),
+    }
+}
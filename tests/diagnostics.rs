@@ -0,0 +1,74 @@
+use horba::Vm;
+use std::fs;
+use std::path::PathBuf;
+
+// A directory of its own for the test's modules.
+fn modules(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("horba-{}-{}", test, std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    for (name, source) in files {
+        fs::write(directory.join(name), source).unwrap();
+    }
+    directory
+}
+
+#[test]
+fn errors_in_a_module_point_into_the_module() {
+    colored::control::set_override(false);
+    let directory = modules(
+        "module-error",
+        &[("lib.hb", "export fn f(x) {\n  return x / \"s\"\n}\n")],
+    );
+    let vm = Vm::builder().search_path(directory.clone()).build();
+
+    let error = vm
+        .eval("import { f } from \"lib\"\nprint f(2)\n")
+        .err()
+        .unwrap();
+    let diagnostics = error.diagnostics();
+    let rendered = diagnostics[0].render(&vm.sources());
+    let json = diagnostics[0].to_json(&vm.sources());
+    fs::remove_dir_all(directory).unwrap();
+
+    assert!(rendered.contains("lib.hb:2:12"), "{}", rendered);
+    assert!(rendered.contains("2 |   return x / \"s\""), "{}", rendered);
+    assert!(
+        rendered.contains("- left operand is Number"),
+        "{}",
+        rendered
+    );
+    assert!(json["file"].as_str().unwrap().ends_with("lib.hb"));
+    assert_eq!(json["code"], "E0202");
+    assert_eq!(json["span"]["line_start"], 2);
+    assert_eq!(json["span"]["column_start"], 12);
+    assert_eq!(json["labels"][1]["span"]["column_end"], 17);
+}
+
+#[test]
+fn errors_in_the_eval_point_into_it() {
+    colored::control::set_override(false);
+    let vm = Vm::new();
+    vm.eval("fn f() {\n  return 1\n}\n").unwrap();
+
+    let error = vm.eval("let x = f()\nprint x / 0\n").err().unwrap();
+    let json = error.diagnostics()[0].to_json(&vm.sources());
+
+    assert_eq!(json["file"], "<eval>");
+    assert_eq!(json["code"], "E0205");
+    assert_eq!(json["span"]["line_start"], 2);
+    assert_eq!(json["labels"][0]["message"], "this is 0");
+}
+
+#[test]
+fn imports_stop_at_the_same_max_errors() {
+    let directory = modules("import-max-errors", &[("lib.hb", &"let = 1\n".repeat(5))]);
+    let vm = Vm::new();
+    vm.interpreter().add_search_path(directory.clone());
+    vm.interpreter().set_max_errors(2);
+
+    let error = vm.eval("import \"lib\"\n").err().unwrap();
+    let diagnostics = error.diagnostics();
+    fs::remove_dir_all(directory).unwrap();
+
+    assert_eq!(diagnostics[0].notes.len(), 2, "{:?}", diagnostics[0].notes);
+}
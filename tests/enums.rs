@@ -0,0 +1,51 @@
+use horba::{Literal, Vm};
+
+const SHAPE: &str = "enum Shape { Circle(radius), Rect(w, h), Empty }\nfn area(s) {\n  return match s {\n    Shape.Circle(r) => 3 * r * r,\n    Rect(w, h) => w * h,\n    Shape.Empty => 0\n  }\n}\n";
+
+fn eval(source: &str) -> Result<Literal, String> {
+    Vm::new()
+        .eval(&format!("{}{}", SHAPE, source))
+        .map_err(|x| x.to_string())
+}
+
+#[test]
+fn variants_print_with_their_enum_and_payload() {
+    assert_eq!(
+        eval("[Shape.Circle(2), Shape.Rect(2, 3), Shape.Empty]")
+            .unwrap()
+            .to_string(),
+        "[Shape.Circle(2), Shape.Rect(2, 3), Shape.Empty]"
+    );
+    assert_eq!(eval("Shape.Rect(2, 3).h").unwrap(), Literal::Number(3.0));
+}
+
+#[test]
+fn match_destructures_the_payload() {
+    assert_eq!(
+        eval("[area(Shape.Circle(2)), area(Shape.Rect(2, 3)), area(Shape.Empty)]")
+            .unwrap()
+            .to_string(),
+        "[12, 6, 0]"
+    );
+}
+
+#[test]
+fn is_checks_the_enum_or_the_variant() {
+    let source = "[Shape.Circle(1) is Shape, Shape.Circle(1) is Shape.Circle, Shape.Empty is Shape.Rect, Shape.Empty is Shape.Empty]";
+    assert_eq!(
+        eval(source).unwrap().to_string(),
+        "[True, True, False, True]"
+    );
+    assert_eq!(
+        eval("Shape.Rect(1, 2) == Shape.Rect(1, 2)").unwrap(),
+        Literal::True
+    );
+}
+
+#[test]
+fn payloads_need_the_right_number_of_values() {
+    assert_eq!(
+        eval("Shape.Circle(1, 2)").unwrap_err(),
+        "Expected 1 arguments but got 2."
+    );
+}
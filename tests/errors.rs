@@ -0,0 +1,23 @@
+use horba::{Literal, Vm};
+
+fn eval(source: &str) -> Literal {
+    Vm::new().eval(source).unwrap()
+}
+
+#[test]
+fn caught_errors_are_instances_of_error() {
+    let source = "let r = Null\ntry { 1 / \"x\" } catch (e) { r = [e is Error, e.message] }\nr\n";
+    assert_eq!(
+        eval(source).to_string(),
+        "[True, Operands of '/' must be Numbers.]"
+    );
+}
+
+#[test]
+fn is_error_goes_by_the_struct_not_its_name() {
+    let source = "struct Error { message, line, column }\nlet r = Null\ntry { 1 / \"x\" } catch (e) { r = e is Error }\nr\n";
+    assert_eq!(eval(source), Literal::False);
+
+    let source = "struct Error { message }\nError(\"mine\") is Error\n";
+    assert_eq!(eval(source), Literal::True);
+}
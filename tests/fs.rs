@@ -0,0 +1,37 @@
+use horba::Vm;
+use std::fs;
+
+#[test]
+fn write_append_read_list_and_remove() {
+    let directory = std::env::temp_dir().join(format!("horba-fs-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let vm = Vm::new();
+    vm.set_global("dir", directory.to_str().unwrap());
+    vm.set_global("file", directory.join("a.txt").to_str().unwrap());
+
+    let source = "fs.write_file(file, \"one\")\nfs.append_file(file, \" two\")\n[fs.read_file(file), fs.exists(file), fs.list_dir(dir)]\n";
+    let written = vm.eval(source).map_err(|x| x.to_string());
+    let source = "fs.remove(file)\n[fs.exists(file), fs.list_dir(dir)]\n";
+    let removed = vm.eval(source).map_err(|x| x.to_string());
+    fs::remove_dir_all(directory).unwrap();
+
+    assert_eq!(written.unwrap().to_string(), "[one two, True, [a.txt]]");
+    assert_eq!(removed.unwrap().to_string(), "[False, []]");
+}
+
+#[test]
+fn failures_are_catchable_and_have_the_os_message() {
+    let vm = Vm::new();
+    let path = std::env::temp_dir().join(format!("horba-fs-missing-{}", std::process::id()));
+    vm.set_global("path", path.to_str().unwrap());
+
+    let source = "let r = Null\ntry { fs.read_file(path) } catch (e) { r = e.message }\nr\n";
+    let message = vm.eval(source).unwrap().to_string();
+    let os_message = fs::read_to_string(&path).unwrap_err().to_string();
+    assert!(
+        message.starts_with(&format!("Couldn't read '{}'", path.display())),
+        "{}",
+        message
+    );
+    assert!(message.contains(&os_message), "{}", message);
+}
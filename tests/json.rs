@@ -0,0 +1,63 @@
+use horba::{Literal, Vm};
+
+const TEXT: &str = r#"{"name": "horba", "tags": ["a", "b"], "nested": {"x": 1.5, "y": null, "z": []}, "ok": true, "odd key": -0.25, "empty": {}}"#;
+
+fn eval(vm: &Vm, source: &str) -> Result<Literal, String> {
+    vm.eval(source).map_err(|x| x.to_string())
+}
+
+#[test]
+fn parse_stringify_parse_round_trips() {
+    let vm = Vm::new();
+    vm.set_global("text", TEXT);
+
+    let source = "let value = json.parse(text)\nlet again = json.parse(json.stringify(value))\n[again == value, json.stringify(again) == json.stringify(value), json.parse(json.stringify(value, 2)) == value]\n";
+    assert_eq!(eval(&vm, source).unwrap().to_string(), "[True, True, True]");
+    assert_eq!(
+        eval(&vm, "json.stringify(value)").unwrap(),
+        Literal::String(
+            r#"{"name":"horba","tags":["a","b"],"nested":{"x":1.5,"y":null,"z":[]},"ok":true,"odd key":-0.25,"empty":{}}"#
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn objects_with_the_same_keys_in_another_order_are_equal() {
+    let vm = Vm::new();
+    vm.set_global("a", r#"{"x": 1, "y": [2]}"#);
+    vm.set_global("b", r#"{"y": [2], "x": 1}"#);
+
+    assert_eq!(
+        eval(&vm, "json.parse(a) == json.parse(b)").unwrap(),
+        Literal::True
+    );
+}
+
+#[test]
+fn stringify_rejects_cycles() {
+    let vm = Vm::new();
+    let source =
+        "struct Node { next }\nlet node = Node(Null)\nnode.next = [node]\njson.stringify(node)\n";
+    assert_eq!(
+        eval(&vm, source).unwrap_err(),
+        "Can't turn a structure that contains itself into JSON."
+    );
+
+    // The same value twice, side by side, isn't a cycle.
+    let source = "let shared = [1]\njson.stringify([shared, shared])\n";
+    assert_eq!(
+        eval(&vm, source).unwrap(),
+        Literal::String("[[1],[1]]".to_string())
+    );
+}
+
+#[test]
+fn stringify_rejects_functions() {
+    let vm = Vm::new();
+    let source = "fn f() {\n  return 1\n}\njson.stringify([1, f])\n";
+    assert_eq!(
+        eval(&vm, source).unwrap_err(),
+        "Can't turn a Function into JSON."
+    );
+}
@@ -0,0 +1,31 @@
+use horba::{EvalError, Vm};
+
+fn eval(source: &str) -> String {
+    Vm::new().eval(source).unwrap().to_string()
+}
+
+#[test]
+fn functions_and_constants() {
+    let source = "[math.sqrt(16), math.pow(2, 10), math.abs(-3), math.floor(1.5), math.ceil(1.5), math.round(2.5)]";
+    assert_eq!(eval(source), "[4, 1024, 3, 1, 2, 3]");
+
+    let source =
+        "[math.min(3, 1), math.max(3, 1), math.sin(0), math.cos(0), math.log(math.E), math.exp(0)]";
+    assert_eq!(eval(source), "[1, 3, 0, 1, 1, 1]");
+
+    let source =
+        "[math.is_nan(math.NAN), math.is_nan(1), math.INF > 1, math.PI > 3.14, math.PI < 3.15]";
+    assert_eq!(eval(source), "[True, False, True, True, True]");
+}
+
+#[test]
+fn type_errors_point_at_the_call() {
+    match Vm::new().eval("let x = 1\nx = math.sqrt(\"x\")\n") {
+        Err(EvalError::Runtime(error)) => {
+            assert_eq!(error.code, "E0202");
+            assert_eq!(error.message, "Expected Number type, got String.");
+            assert_eq!((error.token.line, error.token.lexeme.as_str()), (2, ")"));
+        }
+        other => panic!("{:?}", other.map_err(|x| x.to_string())),
+    }
+}
@@ -0,0 +1,105 @@
+use horba::{object, EvalError, Literal, NativeObject, Vm};
+
+// A counter scripts can read, bump and reconfigure.
+struct Counter {
+    count: f64,
+    step: f64,
+}
+
+impl NativeObject for Counter {
+    fn type_name(&self) -> String {
+        "Counter".to_string()
+    }
+
+    fn get_property(&self, name: &str) -> Option<Literal> {
+        match name {
+            "count" => Some(Literal::Number(self.count)),
+            "step" => Some(Literal::Number(self.step)),
+            _ => None,
+        }
+    }
+
+    fn set_property(&mut self, name: &str, value: Literal) -> Result<(), String> {
+        match (name, value) {
+            ("step", Literal::Number(x)) => {
+                self.step = x;
+                Ok(())
+            }
+            ("step", _) => Err("step must be a Number.".to_string()),
+            _ => Err(format!("Can't set '{}' on Counter.", name)),
+        }
+    }
+
+    fn method_arity(&self, name: &str) -> Option<usize> {
+        match name {
+            "bump" => Some(0),
+            "bump_by" => Some(1),
+            _ => None,
+        }
+    }
+
+    fn call_method(&mut self, name: &str, arguments: Vec<Literal>) -> Result<Literal, String> {
+        match (name, arguments.first()) {
+            ("bump", _) => self.count += self.step,
+            ("bump_by", Some(Literal::Number(x))) if *x >= 0.0 => self.count += x,
+            _ => return Err("Counters only go up.".to_string()),
+        }
+        Ok(Literal::Number(self.count))
+    }
+}
+
+fn vm() -> Vm {
+    let vm = Vm::new();
+    vm.set_global(
+        "counter",
+        object(Counter {
+            count: 0.0,
+            step: 1.0,
+        }),
+    );
+    vm
+}
+
+// The message and where in the script it went wrong.
+fn error(vm: &Vm, source: &str) -> (String, u32, String) {
+    match vm.eval(source) {
+        Err(EvalError::Runtime(error)) => (error.message, error.token.line, error.token.lexeme),
+        other => panic!("{:?}", other.map_err(|x| x.to_string())),
+    }
+}
+
+#[test]
+fn properties_and_methods() {
+    let vm = vm();
+    let source = "counter.bump()\ncounter.step = 5\ncounter.bump()\nlet bump_by = counter.bump_by\nbump_by(2)\n[counter.count, counter.step, counter]\n";
+    assert_eq!(vm.eval(source).unwrap().to_string(), "[8, 5, <Counter>]");
+}
+
+#[test]
+fn errors_point_at_the_script() {
+    let vm = vm();
+    assert_eq!(
+        error(&vm, "let x = 1\ncounter.bump_by(-1)\n"),
+        ("Counters only go up.".to_string(), 2, ")".to_string())
+    );
+    assert_eq!(
+        error(&vm, "let x = 1\ncounter.step = \"fast\"\n"),
+        ("step must be a Number.".to_string(), 2, "step".to_string())
+    );
+    assert_eq!(
+        error(&vm, "counter.count = 1\n"),
+        (
+            "Can't set 'count' on Counter.".to_string(),
+            1,
+            "count".to_string()
+        )
+    );
+    assert_eq!(
+        error(&vm, "\ncounter.missing\n"),
+        (
+            "Undefined property 'missing'.".to_string(),
+            2,
+            "missing".to_string()
+        )
+    );
+}
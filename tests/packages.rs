@@ -0,0 +1,175 @@
+use horba::manifest::Project;
+use horba::Vm;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// A directory of its own for the test's files, which can be in subdirectories.
+fn files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("horba-{}-{}", test, std::process::id()));
+    for (name, text) in files {
+        let path = directory.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+    fs::canonicalize(directory).unwrap()
+}
+
+// app depends on strs, which depends on chars. strs' entry is lib.hb, not the default.
+fn project(test: &str) -> PathBuf {
+    files(
+        test,
+        &[
+            (
+                "horba.toml",
+                "[package]\nname = \"app\"\n\n[dependencies]\nstrs = { path = \"vendor/strs\" }\n",
+            ),
+            ("src/main.hb", "import \"strs\"\nprint strs\nprint strs.twice(\"hi\")\n"),
+            (
+                "vendor/strs/horba.toml",
+                "[package]\nname = \"strs\"\nentry = \"src/lib.hb\"\n\n[dependencies]\nchars = { path = \"../chars\" }\n",
+            ),
+            (
+                "vendor/strs/src/lib.hb",
+                "import \"chars\"\nexport fn twice(x) {\n  return chars.pair(x)\n}\n",
+            ),
+            ("vendor/chars/horba.toml", "[package]\nname = \"chars\"\n"),
+            ("vendor/chars/src/main.hb", "export fn pair(x) {\n  return [x, x]\n}\n"),
+        ],
+    )
+}
+
+fn vm(project: &Project) -> Vm {
+    let vm = Vm::new();
+    let interpreter = vm.interpreter();
+    interpreter.add_package(&project.package);
+    for dependency in project.dependencies.iter() {
+        interpreter.add_package(dependency);
+    }
+    interpreter.set_script_path(&project.package.entry);
+    vm
+}
+
+#[test]
+fn loads_the_manifest_and_every_dependency() {
+    let root = project("manifest");
+    let project = Project::find(&root.join("src")).unwrap();
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(project.package.name, "app");
+    assert_eq!(project.package.root, root);
+    assert_eq!(project.package.entry, root.join("src/main.hb"));
+    assert_eq!(project.package.source_roots, vec![root.join("src")]);
+    assert_eq!(project.package.dependencies, vec!["strs"]);
+
+    // Depth first, so a package comes after what it depends on.
+    let names: Vec<&str> = project
+        .dependencies
+        .iter()
+        .map(|x| x.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["chars", "strs"]);
+    assert_eq!(
+        project.dependencies[1].entry,
+        root.join("vendor/strs/src/lib.hb")
+    );
+    assert_eq!(project.dependencies[1].dependencies, vec!["chars"]);
+}
+
+#[test]
+fn rejects_bad_manifests() {
+    let error = |files: &[(&str, &str)], test: &str| {
+        let root = self::files(test, files);
+        let error = Project::load(&root).err().unwrap().to_string();
+        fs::remove_dir_all(&root).unwrap();
+        error
+    };
+
+    let message = error(
+        &[("horba.toml", "[package]\nname = \"a\"\nversion = \"1\"\n")],
+        "manifest-unknown",
+    );
+    assert!(message.contains("unknown field `version`"), "{}", message);
+
+    let message = error(
+        &[
+            (
+                "horba.toml",
+                "[package]\nname = \"a\"\n\n[dependencies]\nb = { path = \"b\" }\n",
+            ),
+            ("b/horba.toml", "[package]\nname = \"c\"\n"),
+        ],
+        "manifest-misnamed",
+    );
+    assert!(
+        message.ends_with("is named 'c' in its horba.toml."),
+        "{}",
+        message
+    );
+
+    let message = error(
+        &[(
+            "horba.toml",
+            "[package]\nname = \"a\"\n\n[dependencies]\nb = { path = \"nowhere\" }\n",
+        )],
+        "manifest-missing",
+    );
+    assert!(
+        message.starts_with("Couldn't find dependency 'b'"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn dependencies_are_named_after_the_package() {
+    let root = project("resolve");
+    let project = Project::find(&root).unwrap();
+    let vm = vm(&project);
+
+    let result = vm.eval("import \"strs\"\n[strs, strs.twice(1)]\n");
+    fs::remove_dir_all(&root).unwrap();
+    assert_eq!(result.unwrap().to_string(), "[<module strs>, [1, 1]]");
+}
+
+#[test]
+fn only_declared_dependencies_can_be_imported() {
+    let root = project("scope");
+    let project = Project::find(&root).unwrap();
+    let vm = vm(&project);
+
+    // chars is only a dependency of strs.
+    let error = vm.eval("import \"chars\"\n").err().unwrap().to_string();
+    fs::remove_dir_all(&root).unwrap();
+    assert!(
+        error.starts_with("Couldn't find module 'chars'"),
+        "{}",
+        error
+    );
+}
+
+fn horba(directory: &Path) -> (String, String, Option<i32>) {
+    let output = Command::new(env!("CARGO_BIN_EXE_horba"))
+        .arg("run")
+        .arg(directory)
+        .output()
+        .unwrap();
+    (
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+        output.status.code(),
+    )
+}
+
+#[test]
+fn horba_run_runs_the_entry() {
+    let root = project("run");
+    let (stdout, stderr, code) = horba(&root);
+    let (_, missing, missing_code) = horba(&root.join("vendor/chars/src/nowhere"));
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(stdout, "<module strs>\n[hi, hi]\n", "{}", stderr);
+    assert_eq!(code, Some(0));
+    assert!(missing.starts_with("Couldn't open"), "{}", missing);
+    assert_eq!(missing_code, Some(66));
+}
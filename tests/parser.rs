@@ -0,0 +1,149 @@
+use horba::parser::{self, MAX_ERRORS};
+use horba::scanner::token::SourceId;
+
+// Every syntax error in the source, as (line, column, message).
+fn errors(source: &str) -> Vec<(u32, u32, String)> {
+    match parser::parse_source(source, SourceId::default(), MAX_ERRORS) {
+        Ok(_) => Vec::new(),
+        Err(diagnostics) => diagnostics
+            .into_iter()
+            .map(|x| (x.span.line, x.span.column, x.message))
+            .collect(),
+    }
+}
+
+fn error(line: u32, column: u32, message: &str) -> (u32, u32, String) {
+    (line, column, message.to_string())
+}
+
+#[test]
+fn recovers_at_the_next_line() {
+    let source = "let x = 1 +\nprint x\nlet = 3\nprint )\nlet y = 2\n";
+    assert_eq!(
+        errors(source),
+        vec![
+            error(2, 1, "Expect expression."),
+            error(3, 5, "Expect pattern."),
+            error(4, 7, "Expect expression."),
+        ]
+    );
+}
+
+#[test]
+fn recovers_inside_a_function_body() {
+    let source = "fn f(x) {\n  let y = )\n  return x\n}\nprint f(1)\n";
+    assert_eq!(errors(source), vec![error(2, 11, "Expect expression.")]);
+}
+
+#[test]
+fn recovers_inside_nested_blocks() {
+    let source =
+        "fn f() {\n  while true {\n    print )\n    let = 1\n  }\n  return 2\n}\nlet z = )\n";
+    assert_eq!(
+        errors(source),
+        vec![
+            error(3, 11, "Expect expression."),
+            error(4, 9, "Expect pattern."),
+            error(8, 9, "Expect expression."),
+        ]
+    );
+}
+
+#[test]
+fn skips_braces_opened_by_the_bad_statement() {
+    let source = "try { print json.stringify({\"f\": clock}) } catch (e) { print e }\nprint 2 +\n";
+    assert_eq!(
+        errors(source),
+        vec![
+            error(1, 28, "Expect expression."),
+            error(3, 1, "Expect expression."),
+        ]
+    );
+
+    let source = "fn f() {\n  if (1 +) {\n    print 1\n  }\n  return 2\n}\n";
+    assert_eq!(errors(source), vec![error(2, 10, "Expect expression.")]);
+}
+
+#[test]
+fn reports_an_unclosed_block_once() {
+    let source = "fn f() {\n  print 1\n";
+    assert_eq!(errors(source), vec![error(3, 1, "Expect '}' after block.")]);
+}
+
+#[test]
+fn stops_at_max_errors() {
+    let source = "print )\n".repeat(5);
+    let diagnostics = parser::parse_source(&source, SourceId::default(), 3)
+        .err()
+        .unwrap();
+
+    assert_eq!(diagnostics.len(), 3);
+    assert_eq!(
+        diagnostics[2].notes,
+        vec!["Stopped after 3 errors, the rest of the file wasn't checked."]
+    );
+}
+
+#[test]
+fn doesnt_recurse_forever_at_the_end() {
+    assert_eq!(errors("print ("), vec![error(1, 8, "Expect expression.")]);
+}
+
+#[test]
+fn reports_scanner_and_parser_errors_together() {
+    let source = "let x = 1 +\nprint @ 2\nlet = 3\n";
+    assert_eq!(
+        errors(source),
+        vec![
+            error(2, 1, "Expect expression."),
+            error(2, 7, "Unexpected character: @"),
+            error(3, 5, "Expect pattern."),
+        ]
+    );
+}
+
+#[test]
+fn scanner_errors_count_towards_max_errors() {
+    let source = "print @\nprint )\nprint #\nprint )\n";
+    let diagnostics = parser::parse_source(source, SourceId::default(), 2)
+        .err()
+        .unwrap();
+
+    let messages: Vec<&str> = diagnostics.iter().map(|x| x.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec!["Unexpected character: @", "Expect expression."]
+    );
+    assert_eq!(
+        diagnostics[1].notes,
+        vec!["Stopped after 2 errors, the rest of the file wasn't checked."]
+    );
+
+    let source = "@ # $ %\n";
+    let diagnostics = parser::parse_source(source, SourceId::default(), 3)
+        .err()
+        .unwrap();
+    assert_eq!(diagnostics.len(), 3);
+    assert_eq!(diagnostics[2].notes.len(), 1);
+}
+
+#[test]
+fn lines_stay_right_after_a_bad_character() {
+    let source = "let x = 1@\nlet = 2\n";
+    assert_eq!(
+        errors(source),
+        vec![
+            error(1, 10, "Unexpected character: @"),
+            error(2, 5, "Expect pattern."),
+        ]
+    );
+
+    // The character right after it is scanned too.
+    assert_eq!(
+        errors("print @)\n"),
+        vec![
+            error(1, 7, "Unexpected character: @"),
+            error(1, 8, "Expect expression."),
+        ]
+    );
+}
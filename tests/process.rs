@@ -0,0 +1,36 @@
+use horba::Vm;
+
+#[test]
+fn env_and_cwd() {
+    let vm = Vm::new();
+    let cwd = std::env::current_dir().unwrap();
+    vm.set_global("path", std::env::var("PATH").unwrap());
+    let source = "[os.env(\"PATH\") == path, os.env(\"HORBA_SURELY_NOT_SET\"), os.cwd()]";
+    assert_eq!(
+        vm.eval(source).unwrap().to_string(),
+        format!("[True, Null, {}]", cwd.display())
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn run_gives_back_stdout_stderr_and_the_status() {
+    let vm = Vm::new();
+    let source = "let out = process.run(\"sh\", [\"-c\", \"echo out; echo err >&2; exit 3\"])\n[out is process.Output, out.stdout, out.stderr, out.status]\n";
+    assert_eq!(
+        vm.eval(source).unwrap().to_string(),
+        "[True, out\n, err\n, 3]"
+    );
+}
+
+#[test]
+fn programs_that_dont_start_are_errors() {
+    let vm = Vm::new();
+    let source = "let r = Null\ntry { process.run(\"horba-surely-not-a-program\") } catch (e) { r = e.message }\nr\n";
+    let message = vm.eval(source).unwrap().to_string();
+    assert!(
+        message.starts_with("Couldn't run 'horba-surely-not-a-program': "),
+        "{}",
+        message
+    );
+}
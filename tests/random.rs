@@ -0,0 +1,37 @@
+use horba::{Literal, Vm};
+
+const DRAWS: &str = "[random.int(1, 100), random.int(1, 100), random.int(1, 100), random.int(1, 100), random.random(), random.random()]";
+
+#[test]
+fn the_same_seed_gives_the_same_numbers() {
+    let vm = Vm::new();
+    let first = vm.eval(&format!("random.seed(42)\n{}\n", DRAWS)).unwrap();
+    let again = vm.eval(&format!("random.seed(42)\n{}\n", DRAWS)).unwrap();
+    let other = vm.eval(&format!("random.seed(43)\n{}\n", DRAWS)).unwrap();
+
+    assert_eq!(first, again);
+    assert_ne!(first, other);
+    // And in a new interpreter, on any platform, since the generator is the crate's own.
+    assert_eq!(
+        Vm::new()
+            .eval(&format!("random.seed(42)\n{}\n", DRAWS))
+            .unwrap(),
+        first
+    );
+    assert_eq!(
+        Vm::new().eval("random.seed(42)\n[random.int(1, 100), random.int(1, 100), random.int(1, 100), random.int(1, 100)]\n").unwrap().to_string(),
+        "[45, 80, 25, 70]"
+    );
+}
+
+#[test]
+fn floats_are_in_zero_to_one() {
+    let vm = Vm::new();
+    vm.eval("random.seed(7)\n").unwrap();
+    for _ in 0..100 {
+        match vm.eval("random.random()").unwrap() {
+            Literal::Number(x) => assert!((0.0..1.0).contains(&x), "{}", x),
+            other => panic!("{}", other),
+        }
+    }
+}
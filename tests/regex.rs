@@ -0,0 +1,49 @@
+use horba::{EvalError, Vm};
+
+fn eval(source: &str) -> String {
+    Vm::new().eval(source).unwrap().to_string()
+}
+
+#[test]
+fn find_and_find_all_give_text_and_positions() {
+    assert_eq!(
+        eval("regex.find(\"\\d+\", \"ab 12 cd 345\")"),
+        "Object { text: 12, start: 3, end: 5 }"
+    );
+    assert_eq!(eval("regex.find(\"\\d+\", \"none\")"), "Null");
+    assert_eq!(
+        eval("regex.find_all(\"\\d+\", \"ab 12 cd 345\")"),
+        "[Object { text: 12, start: 3, end: 5 }, Object { text: 345, start: 9, end: 12 }]"
+    );
+    // Positions are in characters, not bytes.
+    assert_eq!(eval("regex.find(\"\\d\", \"é1\").start"), "1");
+}
+
+#[test]
+fn captures_by_number_and_name() {
+    let source = "let r = regex.compile(\"(?P<y>\\d{4})-(?P<m>\\d\\d)\")\n[r.pattern, r.is_match(\"on 2024-02\"), r.captures(\"on 2024-02 and\"), r.captures(\"none\")]\n";
+    assert_eq!(
+        eval(source),
+        "[(?P<y>\\d{4})-(?P<m>\\d\\d), True, Object { 0: 2024-02, 1: 2024, 2: 02, y: 2024, m: 02 }, Null]"
+    );
+}
+
+#[test]
+fn replace_fills_in_groups() {
+    assert_eq!(
+        eval("regex.replace(\"(?P<n>\\d+)\", \"a1 b22\", \"<$1|${n}>\")"),
+        "a<1|1> b<22|22>"
+    );
+}
+
+#[test]
+fn invalid_patterns_point_at_the_call() {
+    match Vm::new().eval("let ok = 1\nregex.compile(\"(\")\n") {
+        Err(EvalError::Runtime(error)) => {
+            assert_eq!(error.code, "E0211");
+            assert_eq!(error.message, "Invalid regex '(': unclosed group.");
+            assert_eq!((error.token.line, error.token.lexeme.as_str()), (2, ")"));
+        }
+        other => panic!("{:?}", other.map_err(|x| x.to_string())),
+    }
+}
@@ -0,0 +1,28 @@
+use horba::{Literal, Vm};
+
+fn eval(source: &str) -> Result<Literal, String> {
+    Vm::new().eval(source).map_err(|x| x.to_string())
+}
+
+#[test]
+fn propagate_unwraps_ok_and_returns_err() {
+    let source = "fn f(r) {\n  let x = r?\n  return x + 1\n}\n[f(Ok(1)), f(Err(\"no\"))]\n";
+    assert_eq!(eval(source).unwrap().to_string(), "[2, Result.Err(no)]");
+}
+
+#[test]
+fn propagate_only_works_with_the_built_in_result() {
+    let source =
+        "enum Result { Ok(value), Err(error) }\nfn f() {\n  return Result.Ok(1)?\n}\nf()\n";
+    let error = eval(source).unwrap_err();
+    assert!(error.contains("Expected Result type"), "{}", error);
+}
+
+#[test]
+fn propagate_in_a_generator_hands_out_the_err() {
+    let source = "fn* g() {\n  yield 1\n  let y = Err(\"lost\")?\n  yield 2\n}\nlet count = 0\nlet last = Null\nfor x in g() {\n  count = count + 1\n  last = x\n}\nlet it = g()\nit.next()\n[count, last, it.next(), it.done, it.next()]\n";
+    assert_eq!(
+        eval(source).unwrap().to_string(),
+        "[2, Result.Err(lost), Result.Err(lost), True, Null]"
+    );
+}
@@ -0,0 +1,88 @@
+use horba::{Clock, Vm};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+type Log = Rc<RefCell<Vec<(String, f64)>>>;
+
+// A Vm on the virtual clock, where log(label) records the label and the time it was called
+// at, in milliseconds.
+fn vm() -> (Vm, Log) {
+    let log: Log = Rc::new(RefCell::new(Vec::new()));
+    let vm = Vm::builder().clock(Clock::new_virtual()).build();
+    let entries = log.clone();
+    vm.register_fn("record", move |label: String, time: f64| {
+        entries.borrow_mut().push((label, time));
+    });
+    vm.eval("fn log(label) {\n  record(label, clock() * 1000)\n}\n")
+        .unwrap();
+    (vm, log)
+}
+
+fn entry(label: &str, time: f64) -> (String, f64) {
+    (label.to_string(), time)
+}
+
+#[test]
+fn sleeps_wake_in_time_order() {
+    let (vm, log) = vm();
+    let source = "async fn after(ms, label) {\n  await sleep(ms)\n  log(label)\n}\nspawn(after(30, \"c\"))\nspawn(after(10, \"a\"))\nspawn(after(20, \"b\"))\nlog(\"main\")\n";
+    vm.eval(source).unwrap();
+
+    assert_eq!(
+        *log.borrow(),
+        vec![
+            entry("main", 0.0),
+            entry("a", 10.0),
+            entry("b", 20.0),
+            entry("c", 30.0)
+        ]
+    );
+}
+
+#[test]
+fn same_time_wakes_in_the_order_they_slept() {
+    let (vm, log) = vm();
+    let source = "async fn after(ms, label) {\n  await sleep(ms)\n  log(label)\n}\nfor label in [\"x\", \"y\", \"z\"] {\n  spawn(after(5, label))\n}\n";
+    vm.eval(source).unwrap();
+
+    assert_eq!(
+        *log.borrow(),
+        vec![entry("x", 5.0), entry("y", 5.0), entry("z", 5.0)]
+    );
+}
+
+#[test]
+fn tasks_interleave_at_each_sleep() {
+    let (vm, log) = vm();
+    let source = "async fn ticker(label, every, times) {\n  let i = 0\n  while i < times {\n    await sleep(every)\n    log(label)\n    i = i + 1\n  }\n  return label\n}\nlet slow = spawn(ticker(\"slow\", 15, 2))\nlet fast = spawn(ticker(\"fast\", 10, 3))\nlet first = await slow\nlog(first)\nlet second = await fast\nlog(second)\n";
+    vm.eval(source).unwrap();
+
+    assert_eq!(
+        *log.borrow(),
+        vec![
+            entry("fast", 10.0),
+            entry("slow", 15.0),
+            entry("fast", 20.0),
+            entry("slow", 30.0),
+            // Main carries on as soon as slow is done, before fast's last tick at the same time.
+            entry("slow", 30.0),
+            entry("fast", 30.0),
+            entry("fast", 30.0),
+        ]
+    );
+}
+
+#[test]
+fn failed_tasks_keep_their_error_code() {
+    let (vm, _) = vm();
+    let source = "async fn divide(x) {\n  await sleep(1)\n  return x / 0\n}\nspawn(divide(1))\n";
+    let error = vm.eval(source).err().unwrap();
+
+    let diagnostics = error.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, "E0205");
+    assert_eq!(
+        diagnostics[0].notes,
+        vec!["This happened in a spawned task that nothing awaited."]
+    );
+}
@@ -0,0 +1,32 @@
+use horba::{EvalError, Literal, Vm};
+
+#[test]
+fn format_and_parse_round_trip() {
+    let vm = Vm::new();
+    let source = "let t = time.parse(\"2024-02-29T13:05:09.250+01:00\")\n[time.format(t), time.parse(time.format(t)) == t]\n";
+    assert_eq!(
+        vm.eval(source).unwrap().to_string(),
+        "[2024-02-29T12:05:09.250Z, True]"
+    );
+    assert_eq!(
+        vm.eval("time.format(time.parse(\"9999-12-31T23:59:59.999Z\"))")
+            .unwrap(),
+        Literal::String("9999-12-31T23:59:59.999Z".to_string())
+    );
+}
+
+#[test]
+fn format_rejects_times_outside_the_years_it_can_write() {
+    let vm = Vm::new();
+    for time in [
+        "100000000000000000000",
+        "-100000000000000000000",
+        "math.NAN",
+        "time.parse(\"0000-01-01\") - 1",
+    ] {
+        match vm.eval(&format!("time.format({})", time)) {
+            Err(EvalError::Runtime(error)) => assert_eq!(error.code, "E0211", "{}", time),
+            other => panic!("{}: {:?}", time, other.map_err(|x| x.to_string())),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use horba::{EvalError, Literal, Vm};
+
+#[test]
+fn host_functions_and_globals() {
+    let vm = Vm::builder()
+        .register_fn("add", |a: f64, b: f64| a + b)
+        .register_fn("greet", |name: String| format!("hello {}", name))
+        .build();
+    vm.register_fn("halve", |x: f64| match x % 2.0 == 0.0 {
+        true => Ok(x / 2.0),
+        false => Err(format!("{} is odd", x)),
+    });
+    vm.set_global("x", 2.0);
+
+    assert_eq!(vm.eval("add(x, 3)").unwrap(), Literal::Number(5.0));
+    assert_eq!(
+        vm.eval("greet(\"vm\")").unwrap(),
+        Literal::String("hello vm".to_string())
+    );
+    assert_eq!(vm.eval("halve(4)").unwrap(), Literal::Number(2.0));
+
+    // What one eval declares is there for the next, and for the host.
+    vm.eval("let y = add(x, x)\nfn twice(n) {\n  return n * 2\n}\n")
+        .unwrap();
+    assert_eq!(vm.get_global("y"), Some(Literal::Number(4.0)));
+    assert_eq!(vm.eval("twice(y)").unwrap(), Literal::Number(8.0));
+    assert_eq!(vm.get_global("nothing"), None);
+}
+
+#[test]
+fn host_function_errors_are_runtime_errors() {
+    let vm = Vm::builder()
+        .register_fn("add", |a: f64, b: f64| a + b)
+        .register_fn("fail", || Err::<f64, _>("it broke"))
+        .build();
+
+    match vm.eval("add(1, \"2\")") {
+        Err(EvalError::Runtime(x)) => {
+            assert_eq!(x.to_string(), "Expected Number type, got String.")
+        }
+        x => panic!("{:?}", x),
+    }
+    match vm.eval("add(1)") {
+        Err(EvalError::Runtime(x)) => assert_eq!(x.code, "E0206"),
+        x => panic!("{:?}", x),
+    }
+    match vm.eval("fail()") {
+        Err(EvalError::Runtime(x)) => assert_eq!(x.to_string(), "it broke"),
+        x => panic!("{:?}", x),
+    }
+    // A failed eval doesn't stop the next one.
+    assert_eq!(vm.eval("add(1, 2)").unwrap(), Literal::Number(3.0));
+}
+
+#[test]
+fn parse_errors_come_back_as_diagnostics() {
+    let vm = Vm::new();
+    match vm.eval("let = 1\nprint )\n") {
+        Err(EvalError::Parse(x)) => assert_eq!(x.len(), 2),
+        x => panic!("{:?}", x),
+    }
+}
+
+#[test]
+fn warnings_are_kept_until_taken() {
+    let vm = Vm::new();
+    let source = "enum E { A, B }\nlet r = match E.A {\n  E.A => 1\n}\nr\n";
+    assert_eq!(vm.eval(source).unwrap(), Literal::Number(1.0));
+    assert!(vm.eval(&format!("{}r / \"s\"\n", source)).is_err());
+
+    let warnings = vm.take_warnings();
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0].code, "W0100");
+    assert_eq!(warnings[0].message, "Match may not be exhaustive.");
+    assert!(vm.take_warnings().is_empty());
+}